@@ -1,13 +1,13 @@
 use bevy::prelude::*;
 use rand::{rngs::ThreadRng, Rng};
 
-use crate::{movement::{Acceleration, Velocity}, scheduling::GameSchedule, ship::PlayerShip};
+use crate::{ai::AiTransitionEvent, collision_detection::CollisionLayer, movement::{Acceleration, Velocity}, rng::GameRng, scheduling::GameSchedule, ship::PlayerShip};
 
 pub struct ActionPlugin;
 
 impl Plugin for ActionPlugin{
   fn build(&self, app: &mut App) {
-    app.add_systems(Update, (do_drift, do_player_proximity_test, do_track_to_target ).in_set(GameSchedule::EntityUpdates));
+    app.add_systems(FixedUpdate, (do_drift, do_player_proximity_test, do_track_to_target ).in_set(GameSchedule::EntityUpdates));
   }
 }
 
@@ -17,23 +17,56 @@ impl Plugin for ActionPlugin{
 pub struct TrackToTarget{
   target:Entity,
   linear_acceleration:f32,
-  update_timer:Timer
+  update_timer:Timer,
+  //goes after the nearest enemy rather than the nearest player once the target's gone
+  hunts_enemies:bool,
 }
 
 impl TrackToTarget{
   pub fn new( target:Entity, linear_acceleration:f32, update_secs:f32)->Self{
-    Self{ target, linear_acceleration, update_timer:Timer::from_seconds(update_secs, TimerMode::Repeating) }
+    Self{ target, linear_acceleration, update_timer:Timer::from_seconds(update_secs, TimerMode::Repeating), hunts_enemies:false }
+  }
+
+  pub fn hunting_enemies(mut self)->Self{
+    self.hunts_enemies = true;
+    self
   }
 }
 
+//the closest of some candidates to a point, and how far away squared
+fn nearest<'a>(translation:Vec3, candidates:impl Iterator<Item = (&'a GlobalTransform, Entity)>)->Option<(Entity, f32)>{
+  candidates
+    .map(|(candidate_transform, candidate)| (candidate, translation.distance_squared(candidate_transform.translation())))
+    .min_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+//the player ship closest to a point, if any are flying
+fn nearest_player(translation:Vec3, player_query:&Query<(&GlobalTransform, Entity), With<PlayerShip>>)->Option<(Entity, f32)>{
+  nearest(translation, player_query.iter())
+}
+
 fn do_track_to_target(
   mut query:Query<(&mut TrackToTarget, &GlobalTransform, &Velocity, &mut Acceleration)>,
  target_query:Query<&GlobalTransform>, 
+ player_query:Query<(&GlobalTransform, Entity), With<PlayerShip>>,
+ enemy_query:Query<(&GlobalTransform, Entity, &CollisionLayer)>,
  time:Res<Time>
 ){
   for (mut track_to_target, transform, velocity, mut acceleration) in query.iter_mut(){
     track_to_target.update_timer.tick(time.delta());
     if track_to_target.update_timer.just_finished(){
+      //target's gone, go after whoever's nearest instead
+      if !target_query.contains(track_to_target.target){
+        let found = if track_to_target.hunts_enemies{
+          //only what's still on the enemy layer, not captives
+          let enemies = enemy_query.iter().filter(|(_, _, layer)| **layer == CollisionLayer::Enemy);
+          nearest(transform.translation(), enemies.map(|(enemy_transform, enemy, _)| (enemy_transform, enemy)))
+        } else{
+          nearest_player(transform.translation(), &player_query)
+        };
+        let Some((target, _)) = found else{ continue; };
+        track_to_target.target = target;
+      }
       let Ok(target_transform) = target_query.get(track_to_target.target) else{ continue; } ;
       let target_velocity = (target_transform.translation() - transform.translation()).normalize() * acceleration.max_speed;
       let diff = (target_velocity - velocity.0).normalize();
@@ -48,33 +81,33 @@ fn do_track_to_target(
 pub struct PlayerProximityTest{
   trigger_distance_squared:f32,
   test_timer:Timer,
-  action:fn(&mut Commands, Entity, Entity),
+  //ai behaviour to switch to when triggered
+  then:String,
 }
 
 impl PlayerProximityTest{
-  pub fn new ( trigger_distance_squared:f32, test_frequency_seconds:f32, action:fn(&mut Commands, Entity, Entity) )->Self{
+  pub fn new ( trigger_distance_squared:f32, test_frequency_seconds:f32, then:String )->Self{
     Self{ 
       trigger_distance_squared,
       test_timer:Timer::from_seconds(test_frequency_seconds, TimerMode::Repeating), 
-      action,
+      then,
     }
   }
 }
 
 fn do_player_proximity_test(
-  mut commands:Commands, 
   mut query:Query<(&mut PlayerProximityTest, &GlobalTransform, Entity)>,
   player_query:Query<(&GlobalTransform, Entity), With<PlayerShip>>,
   time:Res<Time>,
+  mut ev_transition_writer:EventWriter<AiTransitionEvent>,
 ){
   for (mut proximity_test, transform, entity) in query.iter_mut(){
     proximity_test.test_timer.tick(time.delta());
     if proximity_test.test_timer.just_finished(){
-      for (player_transform, player) in player_query{
-        let diff = transform.translation() - player_transform.translation();
-        if diff.length_squared() < proximity_test.trigger_distance_squared{
-          (proximity_test.action)(&mut commands, entity, player);
-        }
+      //nearest player that's close enough becomes the target
+      let Some((player, distance_squared)) = nearest_player(transform.translation(), &player_query) else{ continue; };
+      if distance_squared < proximity_test.trigger_distance_squared{
+        ev_transition_writer.write(AiTransitionEvent::new(entity, proximity_test.then.clone(), Some(player)));
       }
     }
   }
@@ -94,8 +127,7 @@ impl Drift{
   }
 }
 
-fn do_drift(mut query:Query<(&mut Drift, &mut Acceleration)>, time:Res<Time>){
-  let mut rng = rand::thread_rng();
+fn do_drift(mut query:Query<(&mut Drift, &mut Acceleration)>, time:Res<Time>, mut rng:ResMut<GameRng>){
   for (mut drift, mut acceleration) in query.iter_mut(){
     drift.update_timer.tick(time.delta());
     if drift.update_timer.just_finished(){
@@ -0,0 +1,243 @@
+use bevy::{platform::collections::HashMap, prelude::*};
+use serde::Deserialize;
+
+use crate::{
+  actions::{Drift, PlayerProximityTest, TrackToTarget},
+  asset_loader::AssetsLoading,
+  json_asset::{JsonAsset, JsonAssetApp},
+  scheduling::GameSchedule,
+};
+
+const AI_CONFIG_NAMES: [&str; 2] = ["mine", "dropship"];
+
+pub struct AiPlugin;
+
+impl Plugin for AiPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .init_json_asset::<AiConfig>()
+      .init_resource::<AiConfigCollection>()
+      .add_event::<AiTransitionEvent>()
+      .add_systems(Startup, load_ai_configs)
+      .add_systems(
+        FixedUpdate,
+        (start_behaviours, apply_transitions)
+          .chain()
+          .in_set(GameSchedule::EntityUpdates),
+      );
+  }
+}
+
+///named actions and the behaviours built from them, loaded from data/ai/*.aiconfig.json
+#[derive(Asset, TypePath, Deserialize)]
+pub struct AiConfig {
+  actions: Vec<AiActionData>,
+  behaviour: Vec<AiBehaviourData>,
+}
+
+impl JsonAsset for AiConfig {
+  const EXTENSIONS: &'static [&'static str] = &["aiconfig.json"];
+}
+
+impl AiConfig {
+  fn behaviour(&self, name: &str) -> Option<&AiBehaviourData> {
+    if name.is_empty() {
+      return self.behaviour.first();
+    }
+    self
+      .behaviour
+      .iter()
+      .find(|behaviour| behaviour.name == name)
+  }
+
+  fn action(&self, name: &str) -> Option<&AiActionKind> {
+    self
+      .actions
+      .iter()
+      .find(|action| action.name == name)
+      .map(|action| &action.action)
+  }
+}
+
+#[derive(Deserialize)]
+struct AiActionData {
+  name: String,
+  #[serde(flatten)]
+  action: AiActionKind,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "snake_case")]
+enum AiActionKind {
+  Drift {
+    variance: [f32; 3],
+    trend: [f32; 3],
+    update_secs: f32,
+  },
+  ///switch to the `then` behaviour once a player comes within `distance`
+  PlayerProximity {
+    distance: f32,
+    test_secs: f32,
+    then: String,
+  },
+  ///home in on whoever triggered the switch to this behaviour
+  TrackToTarget {
+    linear_acceleration: f32,
+    update_secs: f32,
+  },
+}
+
+#[derive(Deserialize)]
+struct AiBehaviourData {
+  name: String,
+  actions: Vec<String>,
+}
+
+#[derive(Resource, Default)]
+pub struct AiConfigCollection {
+  configs: HashMap<String, Handle<AiConfig>>,
+}
+
+impl AiConfigCollection {
+  ///behaviour component for an enemy, empty behaviour name starts with the first listed
+  pub fn get_behaviour(&self, config: &str, behaviour: &str) -> AiBehaviour {
+    let Some(handle) = self.configs.get(config) else {
+      warn!("unknown ai config {:?}", config);
+      return AiBehaviour::default();
+    };
+    AiBehaviour {
+      config: handle.clone(),
+      behaviour: behaviour.into(),
+    }
+  }
+}
+
+#[derive(Component, Default)]
+pub struct AiBehaviour {
+  config: Handle<AiConfig>,
+  behaviour: String,
+}
+
+#[derive(Event)]
+pub struct AiTransitionEvent {
+  pub entity: Entity,
+  pub behaviour: String,
+  pub target: Option<Entity>,
+}
+
+impl AiTransitionEvent {
+  pub fn new(entity: Entity, behaviour: String, target: Option<Entity>) -> Self {
+    Self {
+      entity,
+      behaviour,
+      target,
+    }
+  }
+}
+
+fn load_ai_configs(
+  asset_server: Res<AssetServer>,
+  mut collection: ResMut<AiConfigCollection>,
+  mut loading: ResMut<AssetsLoading>,
+) {
+  for name in AI_CONFIG_NAMES {
+    let handle: Handle<AiConfig> = asset_server.load(format!("data/ai/{}.aiconfig.json", name));
+    loading.0.push(handle.clone().untyped());
+    collection.configs.insert(name.into(), handle);
+  }
+}
+
+fn start_behaviours(
+  mut commands: Commands,
+  query: Query<(Entity, &AiBehaviour), Added<AiBehaviour>>,
+  configs: Res<Assets<AiConfig>>,
+) {
+  for (entity, ai_behaviour) in query.iter() {
+    let Some(config) = configs.get(&ai_behaviour.config) else {
+      continue;
+    };
+    apply_behaviour(&mut commands, entity, config, &ai_behaviour.behaviour, None);
+  }
+}
+
+fn apply_transitions(
+  mut commands: Commands,
+  mut ev_transition_reader: EventReader<AiTransitionEvent>,
+  mut query: Query<&mut AiBehaviour>,
+  configs: Res<Assets<AiConfig>>,
+) {
+  for AiTransitionEvent {
+    entity,
+    behaviour,
+    target,
+  } in ev_transition_reader.read()
+  {
+    let Ok(mut ai_behaviour) = query.get_mut(*entity) else {
+      continue;
+    };
+    let Some(config) = configs.get(&ai_behaviour.config) else {
+      continue;
+    };
+    ai_behaviour.behaviour = behaviour.clone();
+    apply_behaviour(&mut commands, *entity, config, behaviour, *target);
+  }
+}
+
+//swap out whatever actions are running for the ones in the named behaviour
+fn apply_behaviour(
+  commands: &mut Commands,
+  entity: Entity,
+  config: &AiConfig,
+  behaviour: &str,
+  target: Option<Entity>,
+) {
+  let Some(behaviour_data) = config.behaviour(behaviour) else {
+    warn!("unknown ai behaviour {:?}", behaviour);
+    return;
+  };
+  let Ok(mut entity_commands) = commands.get_entity(entity) else {
+    return;
+  };
+  entity_commands.remove::<(Drift, PlayerProximityTest, TrackToTarget)>();
+
+  for action_name in behaviour_data.actions.iter() {
+    let Some(action) = config.action(action_name) else {
+      warn!("unknown ai action {:?}", action_name);
+      continue;
+    };
+    match action.clone() {
+      AiActionKind::Drift {
+        variance,
+        trend,
+        update_secs,
+      } => {
+        entity_commands.insert(Drift::new(
+          Vec3::from_array(variance),
+          Vec3::from_array(trend),
+          update_secs,
+        ));
+      }
+      AiActionKind::PlayerProximity {
+        distance,
+        test_secs,
+        then,
+      } => {
+        entity_commands.insert(PlayerProximityTest::new(
+          distance * distance,
+          test_secs,
+          then,
+        ));
+      }
+      AiActionKind::TrackToTarget {
+        linear_acceleration,
+        update_secs,
+      } => {
+        let Some(target) = target else {
+          warn!("ai action {:?} has no target", action_name);
+          continue;
+        };
+        entity_commands.insert(TrackToTarget::new(target, linear_acceleration, update_secs));
+      }
+    }
+  }
+}
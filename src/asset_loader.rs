@@ -1,9 +1,6 @@
-use bevy::{asset::LoadState, prelude::*};
+use bevy::prelude::*;
 use crate::state::{GameState, GameStateEvent};
 
-const BULLET_COLOUR: Color = Color::srgb(2.0, 1.8, 0.2);
-const BULLET_SIZE: f32 = 0.5;
-
 
 #[derive(Resource, Default)]
 pub struct AssetsLoading(pub Vec<UntypedHandle>);
@@ -15,8 +12,6 @@ pub struct SceneAssets {
   pub dropship: Handle<Scene>,
   pub hook: Handle<Scene>,
   pub mine: Handle<Scene>,
-  pub bullet: Handle<Mesh>,
-  pub bullet_material: Handle<StandardMaterial>,
   pub font: Handle<Font>,
 
 }
@@ -57,18 +52,13 @@ fn check_load_state(
   asset_server: Res<AssetServer>,
   mut ev_game_state_writer: EventWriter<GameStateEvent>,
 ){
-  if loading.0.iter().any(|asset| {
-    match asset_server.get_load_state(asset.id()){
-      Some(LoadState::Loaded)=>false,
-      _=>true
-    } 
-  }){
+  if loading.0.iter().any(|asset| !asset_server.is_loaded_with_dependencies(asset.id())){
     return;
   }
   //clear assets ...maybe
   //loading.0.clear();
   //signal ready for game start
-  ev_game_state_writer.write(GameStateEvent::new(GameState::Playing));
+  ev_game_state_writer.write(GameStateEvent::new(GameState::Menu));
 }
 
 
@@ -76,8 +66,6 @@ fn extract_assets(
   mut scene_assets: ResMut<SceneAssets>,
   ship_scene: Res<ShipScene>,
   gltf_assets: Res<Assets<Gltf>>,
-  mut meshes: ResMut<Assets<Mesh>>,
-  mut materials: ResMut<Assets<StandardMaterial>>,
   game_font: Res<GameFont>,
 ) {
   let Some(gltf) = gltf_assets.get(&ship_scene.0) else {
@@ -91,12 +79,6 @@ fn extract_assets(
     dropship: gltf.named_scenes["dropship"].clone(),
     hook: gltf.named_scenes["Claw"].clone(),
     mine: gltf.named_scenes["Mine"].clone(),
-    bullet: meshes.add(
-      Sphere::new(BULLET_SIZE)
-        .mesh()
-        .kind(bevy::render::mesh::SphereKind::Ico { subdivisions: 3 }),
-    ),
-    bullet_material: materials.add(BULLET_COLOUR),
     font: game_font.0.clone(),
   };
 }
@@ -0,0 +1,255 @@
+use bevy::{
+  audio::{DefaultSpatialScale, SpatialScale, Volume},
+  prelude::*,
+};
+
+use crate::{
+  asset_loader::AssetsLoading,
+  bullet::{BulletHitEvent, ShootEvent},
+  death::DeathEvent,
+  hook::{Hook, HookReturnedEvent, Hooked},
+  level::{LevelData, LevelProgress},
+  scheduling::GameSchedule,
+  settings::Settings,
+  ship::PlayerShip,
+  state::{GameState, PauseState},
+};
+
+//play field is ~160 units across, squash it down so sounds pan across the ears
+const AUDIO_SPATIAL_SCALE: f32 = 1. / 20.;
+const AUDIO_EAR_GAP: f32 = 4.;
+
+pub struct GameAudioPlugin;
+
+impl Plugin for GameAudioPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .add_event::<SfxEvent>()
+      .insert_resource(DefaultSpatialScale(SpatialScale::new(AUDIO_SPATIAL_SCALE)))
+      .add_systems(Startup, (load_sounds, add_listener))
+      .add_systems(
+        FixedUpdate,
+        (shot_sounds, hook_sounds).in_set(GameSchedule::PreDespawnEntities),
+      )
+      .add_systems(
+        Update,
+        (
+          play_sfx.run_if(in_state(PauseState::Running)),
+          play_level_music,
+        )
+          .run_if(in_state(GameState::Playing)),
+      )
+      .add_systems(
+        Update,
+        apply_music_volume.run_if(resource_changed::<Settings>),
+      )
+      .add_systems(OnEnter(PauseState::Paused), pause_audio)
+      .add_systems(OnExit(PauseState::Paused), resume_audio)
+      .add_observer(death_sounds);
+  }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Sfx {
+  Shoot,
+  Ricochet,
+  Explosion,
+  HookLaunch,
+  HookCatch,
+  HookReturn,
+  CaptiveEaten,
+  PlayerDeath,
+}
+
+///play a sound effect from a point on the play field
+#[derive(Event)]
+pub struct SfxEvent {
+  pub sfx: Sfx,
+  pub translation: Vec3,
+}
+
+impl SfxEvent {
+  pub fn new(sfx: Sfx, translation: Vec3) -> Self {
+    Self { sfx, translation }
+  }
+}
+
+#[derive(Resource)]
+struct SoundAssets {
+  shoot: Handle<AudioSource>,
+  ricochet: Handle<AudioSource>,
+  explosion: Handle<AudioSource>,
+  hook_launch: Handle<AudioSource>,
+  hook_catch: Handle<AudioSource>,
+  hook_return: Handle<AudioSource>,
+  captive_eaten: Handle<AudioSource>,
+  player_death: Handle<AudioSource>,
+}
+
+impl SoundAssets {
+  fn get(&self, sfx: Sfx) -> Handle<AudioSource> {
+    match sfx {
+      Sfx::Shoot => self.shoot.clone(),
+      Sfx::Ricochet => self.ricochet.clone(),
+      Sfx::Explosion => self.explosion.clone(),
+      Sfx::HookLaunch => self.hook_launch.clone(),
+      Sfx::HookCatch => self.hook_catch.clone(),
+      Sfx::HookReturn => self.hook_return.clone(),
+      Sfx::CaptiveEaten => self.captive_eaten.clone(),
+      Sfx::PlayerDeath => self.player_death.clone(),
+    }
+  }
+}
+
+///the looping background track and the one it's playing
+#[derive(Component)]
+struct Music(Handle<AudioSource>);
+
+fn load_sounds(
+  mut commands: Commands,
+  asset_server: Res<AssetServer>,
+  mut loading: ResMut<AssetsLoading>,
+) {
+  let mut load = |name: &str| {
+    let handle: Handle<AudioSource> = asset_server.load(format!("audio/sfx/{}.wav", name));
+    loading.0.push(handle.clone().untyped());
+    handle
+  };
+  commands.insert_resource(SoundAssets {
+    shoot: load("shoot"),
+    ricochet: load("ricochet"),
+    explosion: load("explosion"),
+    hook_launch: load("hook_launch"),
+    hook_catch: load("hook_catch"),
+    hook_return: load("hook_return"),
+    captive_eaten: load("captive_eaten"),
+    player_death: load("player_death"),
+  });
+}
+
+//hear things from where the camera is looking down from
+fn add_listener(mut commands: Commands, camera: Single<Entity, With<Camera3d>>) {
+  commands
+    .entity(*camera)
+    .insert(SpatialListener::new(AUDIO_EAR_GAP));
+}
+
+fn shot_sounds(
+  mut ev_shoot_reader: EventReader<ShootEvent>,
+  mut ev_bullet_hit_reader: EventReader<BulletHitEvent>,
+  bullet_query: Query<&GlobalTransform>,
+  mut ev_sfx_writer: EventWriter<SfxEvent>,
+) {
+  for shot in ev_shoot_reader.read() {
+    ev_sfx_writer.write(SfxEvent::new(Sfx::Shoot, shot.start));
+  }
+  for hit in ev_bullet_hit_reader.read() {
+    if let Ok(transform) = bullet_query.get(hit.bullet) {
+      ev_sfx_writer.write(SfxEvent::new(Sfx::Ricochet, transform.translation()));
+    }
+  }
+}
+
+fn hook_sounds(
+  launched_query: Query<&GlobalTransform, Added<Hook>>,
+  caught_query: Query<&GlobalTransform, Added<Hooked>>,
+  hook_query: Query<&GlobalTransform, With<Hook>>,
+  mut ev_hook_returned_reader: EventReader<HookReturnedEvent>,
+  mut ev_sfx_writer: EventWriter<SfxEvent>,
+) {
+  for transform in launched_query.iter() {
+    ev_sfx_writer.write(SfxEvent::new(Sfx::HookLaunch, transform.translation()));
+  }
+  for transform in caught_query.iter() {
+    ev_sfx_writer.write(SfxEvent::new(Sfx::HookCatch, transform.translation()));
+  }
+  for HookReturnedEvent { hook, .. } in ev_hook_returned_reader.read() {
+    if let Ok(transform) = hook_query.get(*hook) {
+      ev_sfx_writer.write(SfxEvent::new(Sfx::HookReturn, transform.translation()));
+    }
+  }
+}
+
+fn death_sounds(
+  trigger: Trigger<DeathEvent>,
+  ship_query: Query<(), With<PlayerShip>>,
+  mut ev_sfx_writer: EventWriter<SfxEvent>,
+) {
+  let sfx = if ship_query.contains(trigger.target()) {
+    Sfx::PlayerDeath
+  } else {
+    Sfx::Explosion
+  };
+  ev_sfx_writer.write(SfxEvent::new(sfx, trigger.translation));
+}
+
+fn play_sfx(
+  mut commands: Commands,
+  mut ev_sfx_reader: EventReader<SfxEvent>,
+  sounds: Res<SoundAssets>,
+  settings: Res<Settings>,
+) {
+  let volume = Volume::Linear(settings.sfx_gain());
+  for &SfxEvent { sfx, translation } in ev_sfx_reader.read() {
+    commands.spawn((
+      StateScoped(GameState::Playing),
+      AudioPlayer::new(sounds.get(sfx)),
+      PlaybackSettings::DESPAWN
+        .with_spatial(true)
+        .with_volume(volume),
+      Transform::from_translation(translation),
+    ));
+  }
+}
+
+//swap tracks when the stage changes
+fn play_level_music(
+  mut commands: Commands,
+  progress: Res<LevelProgress>,
+  levels: Res<Assets<LevelData>>,
+  music_query: Query<(Entity, &Music)>,
+  settings: Res<Settings>,
+) {
+  let Some(track) = levels
+    .get(progress.level())
+    .and_then(|level| level.music_track.as_ref())
+  else {
+    return;
+  };
+  let mut playing = false;
+  for (entity, music) in music_query.iter() {
+    if music.0 == *track {
+      playing = true;
+    } else {
+      commands.entity(entity).despawn();
+    }
+  }
+  if playing {
+    return;
+  }
+  commands.spawn((
+    StateScoped(GameState::Playing),
+    Music(track.clone()),
+    AudioPlayer::new(track.clone()),
+    PlaybackSettings::LOOP.with_volume(Volume::Linear(settings.music_gain())),
+  ));
+}
+
+fn apply_music_volume(mut query: Query<&mut AudioSink, With<Music>>, settings: Res<Settings>) {
+  for mut sink in query.iter_mut() {
+    sink.set_volume(Volume::Linear(settings.music_gain()));
+  }
+}
+
+//sound effects still ringing out stop where they are along with the music
+fn pause_audio(query: Query<&AudioSink>) {
+  for sink in query.iter() {
+    sink.pause();
+  }
+}
+
+fn resume_audio(query: Query<&AudioSink>) {
+  for sink in query.iter() {
+    sink.play();
+  }
+}
@@ -0,0 +1,499 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{game_manager::Players, storage};
+
+const BINDINGS_KEY: &str = "bindings";
+const STICK_DEFAULT_DEADZONE: f32 = 0.15;
+const STICK_DEADZONE_STEP: f32 = 0.05;
+const STICK_DEADZONE_STEPS: i32 = 8;
+
+pub struct BindingsPlugin;
+
+impl Plugin for BindingsPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .init_resource::<Bindings>()
+      .init_resource::<Rebinding>()
+      .init_resource::<PlayerPads>()
+      .add_systems(Startup, load_bindings);
+  }
+}
+
+///things the player can do that get bound to buttons
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ControlAction {
+  MoveUp,
+  MoveDown,
+  MoveLeft,
+  MoveRight,
+  Shoot,
+  Recall,
+  Jettison,
+  Consume,
+  Pause,
+}
+
+impl ControlAction {
+  pub const ALL: [ControlAction; 9] = [
+    ControlAction::MoveUp,
+    ControlAction::MoveDown,
+    ControlAction::MoveLeft,
+    ControlAction::MoveRight,
+    ControlAction::Shoot,
+    ControlAction::Recall,
+    ControlAction::Jettison,
+    ControlAction::Consume,
+    ControlAction::Pause,
+  ];
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      ControlAction::MoveUp => "Up",
+      ControlAction::MoveDown => "Down",
+      ControlAction::MoveLeft => "Left",
+      ControlAction::MoveRight => "Right",
+      ControlAction::Shoot => "Hook",
+      ControlAction::Recall => "Recall",
+      ControlAction::Jettison => "Throw captive",
+      ControlAction::Consume => "Eat captive",
+      ControlAction::Pause => "Pause",
+    }
+  }
+}
+
+///a single button on any device
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum Binding {
+  Key(KeyCode),
+  Mouse(MouseButton),
+  Gamepad(GamepadButton),
+}
+
+impl Binding {
+  //rebinding swaps out whatever was bound on the same device
+  fn same_device(&self, other: &Binding) -> bool {
+    std::mem::discriminant(self) == std::mem::discriminant(other)
+  }
+}
+
+///what the letter keys are labelled, keys are bound by where they are on the
+///keyboard so the same bindings work on any layout, only their names change
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default, Debug)]
+pub enum KeyboardLayout {
+  #[default]
+  Qwerty,
+  Azerty,
+}
+
+impl KeyboardLayout {
+  pub fn name(&self) -> &'static str {
+    match self {
+      KeyboardLayout::Qwerty => "QWERTY",
+      KeyboardLayout::Azerty => "AZERTY",
+    }
+  }
+
+  pub fn next(&self) -> Self {
+    match self {
+      KeyboardLayout::Qwerty => KeyboardLayout::Azerty,
+      KeyboardLayout::Azerty => KeyboardLayout::Qwerty,
+    }
+  }
+
+  fn key_name(&self, key: KeyCode) -> String {
+    let azerty = match key {
+      KeyCode::KeyQ => Some("A"),
+      KeyCode::KeyW => Some("Z"),
+      KeyCode::KeyA => Some("Q"),
+      KeyCode::KeyZ => Some("W"),
+      KeyCode::Semicolon => Some("M"),
+      KeyCode::KeyM => Some(","),
+      _ => None,
+    };
+    if let (KeyboardLayout::Azerty, Some(name)) = (self, azerty) {
+      return name.into();
+    }
+    let name = format!("{:?}", key);
+    name
+      .strip_prefix("Key")
+      .or_else(|| name.strip_prefix("Digit"))
+      .unwrap_or(&name)
+      .into()
+  }
+}
+
+///how the stick's pushed off centre gets turned into thrust
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default, Debug)]
+pub enum StickResponse {
+  #[default]
+  Linear,
+  ///gentle near the middle, for fine adjustments
+  Quadratic,
+  Cubic,
+}
+
+impl StickResponse {
+  pub fn name(&self) -> &'static str {
+    match self {
+      StickResponse::Linear => "Linear",
+      StickResponse::Quadratic => "Quadratic",
+      StickResponse::Cubic => "Cubic",
+    }
+  }
+
+  pub fn next(&self) -> Self {
+    match self {
+      StickResponse::Linear => StickResponse::Quadratic,
+      StickResponse::Quadratic => StickResponse::Cubic,
+      StickResponse::Cubic => StickResponse::Linear,
+    }
+  }
+
+  fn apply(&self, amount: f32) -> f32 {
+    match self {
+      StickResponse::Linear => amount,
+      StickResponse::Quadratic => amount * amount,
+      StickResponse::Cubic => amount * amount * amount,
+    }
+  }
+}
+
+///gamepad stick deadzone and response
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct StickTuning {
+  ///how far from centre, 0 to 1, before the stick does anything
+  pub deadzone: f32,
+  pub response: StickResponse,
+}
+
+impl Default for StickTuning {
+  fn default() -> Self {
+    Self {
+      deadzone: STICK_DEFAULT_DEADZONE,
+      response: StickResponse::default(),
+    }
+  }
+}
+
+impl StickTuning {
+  ///stick position to movement, direction kept and length 0 to 1 past the deadzone
+  pub fn apply(&self, stick: Vec2) -> Vec2 {
+    let length = stick.length();
+    if length <= self.deadzone {
+      return Vec2::ZERO;
+    }
+    let amount = ((length - self.deadzone) / (1. - self.deadzone)).min(1.);
+    stick / length * self.response.apply(amount)
+  }
+
+  fn deadzone_steps(&self) -> i32 {
+    (self.deadzone / STICK_DEADZONE_STEP).round() as i32
+  }
+
+  pub fn step_deadzone(&mut self, step: i32) {
+    let steps = (self.deadzone_steps() + step).clamp(0, STICK_DEADZONE_STEPS);
+    self.deadzone = steps as f32 * STICK_DEADZONE_STEP;
+  }
+
+  ///the next deadzone up, wrapping round to none
+  pub fn cycle_deadzone(&mut self) {
+    let steps = (self.deadzone_steps() + 1) % (STICK_DEADZONE_STEPS + 1);
+    self.deadzone = steps as f32 * STICK_DEADZONE_STEP;
+  }
+}
+
+///buttons bound to each action, saved between runs
+#[derive(Resource, Serialize, Deserialize, Clone)]
+pub struct Bindings {
+  pub layout: KeyboardLayout,
+  #[serde(default)]
+  pub stick: StickTuning,
+  actions: Vec<(ControlAction, Vec<Binding>)>,
+}
+
+impl Default for Bindings {
+  fn default() -> Self {
+    use Binding::*;
+    Self {
+      layout: KeyboardLayout::default(),
+      stick: StickTuning::default(),
+      actions: vec![
+        (
+          ControlAction::MoveUp,
+          vec![
+            Key(KeyCode::KeyW),
+            Key(KeyCode::ArrowUp),
+            Gamepad(GamepadButton::DPadUp),
+          ],
+        ),
+        (
+          ControlAction::MoveDown,
+          vec![
+            Key(KeyCode::KeyS),
+            Key(KeyCode::ArrowDown),
+            Gamepad(GamepadButton::DPadDown),
+          ],
+        ),
+        (
+          ControlAction::MoveLeft,
+          vec![
+            Key(KeyCode::KeyA),
+            Key(KeyCode::ArrowLeft),
+            Gamepad(GamepadButton::DPadLeft),
+          ],
+        ),
+        (
+          ControlAction::MoveRight,
+          vec![
+            Key(KeyCode::KeyD),
+            Key(KeyCode::ArrowRight),
+            Gamepad(GamepadButton::DPadRight),
+          ],
+        ),
+        (
+          ControlAction::Shoot,
+          vec![
+            Key(KeyCode::Space),
+            Mouse(MouseButton::Right),
+            Gamepad(GamepadButton::South),
+            Gamepad(GamepadButton::RightTrigger2),
+          ],
+        ),
+        (
+          ControlAction::Recall,
+          vec![
+            Key(KeyCode::KeyR),
+            Gamepad(GamepadButton::East),
+            Gamepad(GamepadButton::LeftTrigger2),
+          ],
+        ),
+        (
+          ControlAction::Jettison,
+          vec![
+            Key(KeyCode::KeyE),
+            Mouse(MouseButton::Middle),
+            Gamepad(GamepadButton::West),
+          ],
+        ),
+        (
+          ControlAction::Consume,
+          vec![Key(KeyCode::KeyC), Gamepad(GamepadButton::North)],
+        ),
+        //android back button comes through as browser back
+        (
+          ControlAction::Pause,
+          vec![
+            Key(KeyCode::Escape),
+            Key(KeyCode::BrowserBack),
+            Gamepad(GamepadButton::Start),
+          ],
+        ),
+      ],
+    }
+  }
+}
+
+impl Bindings {
+  pub fn bound(&self, action: ControlAction) -> &[Binding] {
+    self
+      .actions
+      .iter()
+      .find(|(bound, _)| *bound == action)
+      .map_or(&[], |(_, bindings)| bindings)
+  }
+
+  ///puts the binding on the action in place of any it had on that device
+  pub fn rebind(&mut self, action: ControlAction, binding: Binding) {
+    let Some((_, bindings)) = self.actions.iter_mut().find(|(bound, _)| *bound == action) else {
+      self.actions.push((action, vec![binding]));
+      return;
+    };
+    bindings.retain(|bound| !bound.same_device(&binding));
+    bindings.push(binding);
+  }
+
+  ///back to the default buttons and stick, keeping the keyboard layout
+  pub fn reset(&mut self) {
+    *self = Bindings {
+      layout: self.layout,
+      ..default()
+    };
+  }
+
+  ///the bindings for an action as they'd be shown to the player
+  pub fn label(&self, action: ControlAction) -> String {
+    let names: Vec<_> = self
+      .bound(action)
+      .iter()
+      .map(|binding| match binding {
+        Binding::Key(key) => self.layout.key_name(*key),
+        Binding::Mouse(button) => format!("Mouse {:?}", button),
+        Binding::Gamepad(button) => format!("Pad {:?}", button),
+      })
+      .collect();
+    names.join(" / ")
+  }
+
+  pub fn save(&self) {
+    match serde_json::to_string(self) {
+      Ok(json) => storage::save(BINDINGS_KEY, &json),
+      Err(err) => warn!("failed writing bindings: {:?}", err),
+    }
+  }
+}
+
+///gamepads in the order they were first used, the first one flies the ship
+#[derive(Resource, Default)]
+pub struct PlayerPads(pub Vec<Entity>);
+
+impl PlayerPads {
+  ///the pad a player flies with, in co-op the keyboard has player one
+  ///unless there's a pad for everyone
+  pub fn pad(&self, player: usize, players: usize) -> Option<Entity> {
+    let index = if players <= 1 || self.0.len() >= players {
+      player
+    } else {
+      player.checked_sub(1)?
+    };
+    self.0.get(index).copied()
+  }
+}
+
+///an action waiting for the next button pressed to bind it to
+#[derive(Resource, Default)]
+pub struct Rebinding(pub Option<ControlAction>);
+
+pub fn rebinding(rebinding: Res<Rebinding>) -> bool {
+  rebinding.0.is_some()
+}
+
+fn load_bindings(mut bindings: ResMut<Bindings>) {
+  let Some(json) = storage::load(BINDINGS_KEY) else {
+    return;
+  };
+  match serde_json::from_str(&json) {
+    Ok(loaded) => *bindings = loaded,
+    Err(err) => warn!("failed reading bindings: {:?}", err),
+  }
+}
+
+///button state of every device, looked up through the bindings
+#[derive(SystemParam)]
+pub struct BoundButtons<'w, 's> {
+  bindings: Res<'w, Bindings>,
+  keys: Res<'w, ButtonInput<KeyCode>>,
+  mouse: Res<'w, ButtonInput<MouseButton>>,
+  pads: Res<'w, PlayerPads>,
+  players: Res<'w, Players>,
+  gamepads: Query<'w, 's, &'static Gamepad>,
+}
+
+//keyboard and mouse only ever belong to player one
+impl BoundButtons<'_, '_> {
+  fn pad(&self, player: usize) -> Option<&Gamepad> {
+    self
+      .pads
+      .pad(player, self.players.0)
+      .and_then(|pad| self.gamepads.get(pad).ok())
+  }
+
+  pub fn pressed(&self, player: usize, action: ControlAction) -> bool {
+    self
+      .bindings
+      .bound(action)
+      .iter()
+      .any(|binding| match binding {
+        Binding::Key(key) => player == 0 && self.keys.pressed(*key),
+        Binding::Mouse(button) => player == 0 && self.mouse.pressed(*button),
+        Binding::Gamepad(button) => self
+          .pad(player)
+          .is_some_and(|gamepad| gamepad.pressed(*button)),
+      })
+  }
+
+  pub fn just_pressed(&self, player: usize, action: ControlAction) -> bool {
+    self
+      .bindings
+      .bound(action)
+      .iter()
+      .any(|binding| match binding {
+        Binding::Key(key) => player == 0 && self.keys.just_pressed(*key),
+        Binding::Mouse(button) => player == 0 && self.mouse.just_pressed(*button),
+        Binding::Gamepad(button) => self
+          .pad(player)
+          .is_some_and(|gamepad| gamepad.just_pressed(*button)),
+      })
+  }
+
+  pub fn just_released(&self, player: usize, action: ControlAction) -> bool {
+    self
+      .bindings
+      .bound(action)
+      .iter()
+      .any(|binding| match binding {
+        Binding::Key(key) => player == 0 && self.keys.just_released(*key),
+        Binding::Mouse(button) => player == 0 && self.mouse.just_released(*button),
+        Binding::Gamepad(button) => self
+          .pad(player)
+          .is_some_and(|gamepad| gamepad.just_released(*button)),
+      })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn rebinding_replaces_the_same_device_only() {
+    let mut bindings = Bindings::default();
+    bindings.rebind(ControlAction::MoveUp, Binding::Key(KeyCode::KeyI));
+    assert_eq!(
+      bindings.bound(ControlAction::MoveUp),
+      &[
+        Binding::Gamepad(GamepadButton::DPadUp),
+        Binding::Key(KeyCode::KeyI)
+      ]
+    );
+    assert_eq!(bindings.label(ControlAction::MoveUp), "Pad DPadUp / I");
+  }
+
+  #[test]
+  fn azerty_names_keys_by_position() {
+    let mut bindings = Bindings::default();
+    assert_eq!(
+      bindings.label(ControlAction::MoveLeft),
+      "A / ArrowLeft / Pad DPadLeft"
+    );
+    bindings.layout = KeyboardLayout::Azerty;
+    assert_eq!(
+      bindings.label(ControlAction::MoveLeft),
+      "Q / ArrowLeft / Pad DPadLeft"
+    );
+  }
+
+  #[test]
+  fn stick_ignores_the_deadzone_and_scales_the_rest() {
+    let tuning = StickTuning {
+      deadzone: 0.2,
+      response: StickResponse::Quadratic,
+    };
+    assert_eq!(tuning.apply(Vec2::new(0.15, 0.)), Vec2::ZERO);
+    assert!((tuning.apply(Vec2::new(0., -0.6)) - Vec2::new(0., -0.25)).length() < 1e-5);
+    assert!((tuning.apply(Vec2::new(1., 1.)).length() - 1.).abs() < 1e-5);
+  }
+
+  #[test]
+  fn keyboard_keeps_player_one_in_co_op_until_everyone_has_a_pad() {
+    let first = Entity::from_raw(1);
+    let second = Entity::from_raw(2);
+    let mut pads = PlayerPads(vec![first]);
+    assert_eq!(pads.pad(0, 1), Some(first));
+    assert_eq!(pads.pad(0, 2), None);
+    assert_eq!(pads.pad(1, 2), Some(first));
+    pads.0.push(second);
+    assert_eq!(pads.pad(0, 2), Some(first));
+    assert_eq!(pads.pad(1, 2), Some(second));
+  }
+}
@@ -11,7 +11,7 @@ pub struct BoundsCheckPlugin;
 
 impl Plugin for BoundsCheckPlugin {
   fn build(&self, app: &mut App) {
-    app.add_systems(Update, bounds_check.in_set(GameSchedule::DespawnEntities));
+    app.add_systems(FixedUpdate, bounds_check.in_set(GameSchedule::DespawnEntities));
   }
 }
 
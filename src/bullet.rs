@@ -1,9 +1,12 @@
 use bevy::prelude::*;
 
 use crate::{
-  asset_loader::SceneAssets, bounds_check::BoundsDespawn, collision_detection::Player, effect_sprite::{EffectSpriteEvent, EffectSpriteType}, movement::Velocity, scheduling::GameSchedule
+  actions::TrackToTarget, bounds_check::BoundsDespawn, collision_detection::{CollisionLayer, Swept}, effect_sprite::EffectSpriteEvent, health::DamageSource, movement::{Acceleration, Velocity}, projectile::{Bouncing, Lifetime, ProjectileKind, ProjectileMovement, ProjectileRegistry}, scheduling::GameSchedule
 };
 
+//how often homing shots look for where the target's gone
+const HOMING_UPDATE_SECS: f32 = 0.1;
+
 pub struct BulletPlugin;
 
 impl Plugin for BulletPlugin {
@@ -12,7 +15,7 @@ impl Plugin for BulletPlugin {
       .add_event::<ShootEvent>()
       .add_event::<BulletHitEvent>()
       .add_systems(
-        Update,
+        FixedUpdate,
         (
           do_shooting.in_set(GameSchedule::EntityUpdates),
           bullet_hits.in_set(GameSchedule::DespawnEntities),
@@ -23,8 +26,8 @@ impl Plugin for BulletPlugin {
 
 #[derive(Event)]
 pub struct BulletHitEvent {
-  bullet: Entity,
-  other: Option<Entity>,
+  pub bullet: Entity,
+  pub other: Option<Entity>,
 }
 
 impl BulletHitEvent {
@@ -33,67 +36,132 @@ impl BulletHitEvent {
   }
 }
 
-#[derive(Event)]
+#[derive(Event, Clone)]
 pub struct ShootEvent {
-  pub is_player: bool,
+  pub source: DamageSource,
   pub start: Vec3,
   pub velocity: Vec3,
   pub damage: f32,
   pub scale:f32,
+  ///how many things it hits in all before it stops, so one more than it goes through.
+  ///none is the same as one, a bullet that stops at the first
+  pub piercing: Option<u32>,
+  pub kind: ProjectileKind,
 }
 
 impl ShootEvent {
-  pub fn new(is_player: bool, start: Vec3, velocity: Vec3, damage: f32, scale:f32) -> Self {
+  pub fn new(source: DamageSource, start: Vec3, velocity: Vec3, damage: f32, scale:f32) -> Self {
     Self {
-      is_player,
+      source,
       start,
       velocity,
       damage,
       scale,
+      piercing: None,
+      kind: ProjectileKind::default(),
     }
   }
 }
 
 #[derive(Component)]
-#[require(BoundsDespawn)]
+#[require(BoundsDespawn, Swept)]
 pub struct Bullet {
   //pub hit: bool,
   pub damage: f32,
+  pub source: DamageSource,
+  pub kind: ProjectileKind,
+}
+
+///carries on through what it hits until it's out of hits
+#[derive(Component)]
+pub struct Piercing {
+  pub hits_remaining: u32,
+  //so it doesn't hit the same thing again on the way through
+  hit: Vec<Entity>,
+}
+
+impl Piercing {
+  ///`hits` in all counting the one that stops it, so never less than one
+  pub fn new(hits: u32) -> Self {
+    Self {
+      hits_remaining: hits.max(1),
+      hit: Vec::new(),
+    }
+  }
+
+  pub fn has_hit(&self, entity: Entity) -> bool {
+    self.hit.contains(&entity)
+  }
+
+  //true while it's got hits left to go on with
+  fn pass_through(&mut self, other: Option<Entity>) -> bool {
+    self.hits_remaining = self.hits_remaining.saturating_sub(1);
+    self.hit.extend(other);
+    self.hits_remaining > 0
+  }
 }
 
 fn do_shooting(
   mut commands: Commands,
   mut ev_shoot_events: EventReader<ShootEvent>,
-  scene_assets: Res<SceneAssets>,
+  registry: Res<ProjectileRegistry>,
 ) {
   for &ShootEvent {
-    is_player,
+    source,
     start,
     velocity,
     damage,
     scale,
+    piercing,
+    kind,
   } in ev_shoot_events.read()
   {
 
-    let transform =  Transform::from_translation(start).with_scale(Vec3::new(scale,scale,scale));
-    //FIXME: yuck
-    if is_player {
-      commands.spawn((
-        Bullet { damage },
-        Mesh3d(scene_assets.bullet.clone()),
-        MeshMaterial3d(scene_assets.bullet_material.clone()),
-        transform,
-        Velocity(velocity),
-        Player,
-      ));
+    let Some(def) = registry.get(kind) else {
+      continue;
+    };
+    let player = source.is_player();
+    //pointing the way it's going, for the long ones
+    let transform = Transform::from_translation(start)
+      .with_rotation(Quat::from_rotation_arc(Vec3::X, velocity.normalize_or(Vec3::X)))
+      .with_scale(Vec3::splat(scale));
+    let layer = if player {
+      CollisionLayer::PlayerProjectile
     } else {
-      commands.spawn((
-        Bullet { damage },
-        Mesh3d(scene_assets.bullet.clone()),
-        MeshMaterial3d(scene_assets.bullet_material.clone()),
-        transform,
-        Velocity(velocity),
-      ));
+      CollisionLayer::EnemyProjectile
+    };
+    let mut bullet = commands.spawn((
+      Bullet { damage, source, kind },
+      Mesh3d(def.mesh.clone()),
+      MeshMaterial3d(def.material(player)),
+      transform,
+      Velocity(velocity),
+      layer,
+    ));
+    if let Some(hits) = piercing.or(def.piercing) {
+      bullet.insert(Piercing::new(hits));
+    }
+    if let Some(lifetime) = def.lifetime {
+      bullet.insert(Lifetime(Timer::from_seconds(lifetime, TimerMode::Once)));
+    }
+    match def.movement {
+      ProjectileMovement::Straight => {}
+      ProjectileMovement::Homing { acceleration } => {
+        //no target to start with, it picks the nearest on its first look round
+        let track = TrackToTarget::new(Entity::PLACEHOLDER, acceleration, HOMING_UPDATE_SECS);
+        bullet.insert((
+          Acceleration::new(Vec3::ZERO, 0., velocity.length()),
+          if player { track.hunting_enemies() } else { track },
+        ));
+      }
+      //with no bounces it's just a straight shot
+      ProjectileMovement::Bouncing { bounces: 0 } => {}
+      ProjectileMovement::Bouncing { bounces } => {
+        bullet.insert(Bouncing { bounces_remaining: bounces });
+      }
+      ProjectileMovement::Drifting { damping } => {
+        bullet.insert(Acceleration::new(Vec3::ZERO, damping, velocity.length()));
+      }
     }
   }
 }
@@ -101,25 +169,96 @@ fn do_shooting(
 fn bullet_hits(
   mut commands: Commands, 
   mut ev_bullet_hit_reader: EventReader<BulletHitEvent>,
-  bullet_query: Query<&GlobalTransform>,
+  bullet_query: Query<(&GlobalTransform, &Bullet)>,
   target_query: Query<&Velocity>,
+  registry: Res<ProjectileRegistry>,
+  mut piercing_query: Query<&mut Piercing>,
   mut ev_effect_sprite_writer: EventWriter<EffectSpriteEvent>,
 ) {
+  //a piercing bullet can hit a few things in one step, it still only goes the once
+  let mut spent = Vec::new();
   for hit_event in ev_bullet_hit_reader.read() {
-    
-    if let Ok(transform) = bullet_query.get(hit_event.bullet){
-      let mut velocity = Vec3::ZERO;
-      if hit_event.other.is_some(){
-
-        if let Ok(target_velocity) = target_query.get(hit_event.other.unwrap()){
-          velocity = target_velocity.0;
-        }
-      }
+    //already gone, run out of time or used up earlier in the step
+    if spent.contains(&hit_event.bullet) {
+      continue;
+    }
+    let Ok((transform, bullet)) = bullet_query.get(hit_event.bullet) else {
+      continue;
+    };
+
+    let mut velocity = Vec3::ZERO;
+    if hit_event.other.is_some(){
 
-      ev_effect_sprite_writer.write(EffectSpriteEvent::new(transform.translation(), 1., velocity, EffectSpriteType::Ricochet));
+      if let Ok(target_velocity) = target_query.get(hit_event.other.unwrap()){
+        velocity = target_velocity.0;
+      }
+    }
+    if let Some(def) = registry.get(bullet.kind) {
+      ev_effect_sprite_writer.write(EffectSpriteEvent::new(transform.translation(), def.hit_effect_size, velocity, def.hit_effect));
     }
-    commands.entity(hit_event.bullet).despawn();
+    if let Ok(mut piercing) = piercing_query.get_mut(hit_event.bullet) {
+      if piercing.pass_through(hit_event.other) {
+        continue;
+      }
+    }
+    //bounds_check can have taken it off screen on the same step
+    commands.entity(hit_event.bullet).try_despawn();
+    spent.push(hit_event.bullet);
+
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{headless::HeadlessApp, health::Health};
+
+  //everything's shot along the same row
+  const ROW_Z: f32 = -30.;
+
+  //10 health and a little bigger than a shot, one hit each
+  fn targets(game: &mut HeadlessApp, xs: &[f32]) -> Vec<Entity> {
+    xs.iter()
+      .map(|&x| game.spawn_target(Vec3::new(x, 0., ROW_Z), 1.6, 10.))
+      .collect()
+  }
+
+  fn health(game: &HeadlessApp, entity: Entity) -> f32 {
+    game.world().get::<Health>(entity).unwrap().value
+  }
+
+  #[test]
+  fn bullets_hit_the_nearest_once_unless_they_pierce() {
+    let mut game = HeadlessApp::new();
+    //starting out touching both
+    let overlapping = targets(&mut game, &[0., 2.]);
+    let start = Vec3::new(0.5, 0., ROW_Z);
+    game.send(ShootEvent::new(DamageSource::ShipRam(0), start, Vec3::ZERO, -1., 1.));
+    game.step_frames(4);
+    assert_eq!(health(&game, overlapping[0]), 9.);
+    assert_eq!(health(&game, overlapping[1]), 10.);
+    assert!(game.find::<Bullet>().is_none());
+
+    let in_a_line = targets(&mut game, &[20., 25., 30.]);
+    let velocity = Vec3::new(60., 0., 0.);
+    let start = Vec3::new(10., 0., ROW_Z);
+    let shot = ShootEvent::new(DamageSource::ShipRam(0), start, velocity, -1., 1.);
+    game.send(ShootEvent {
+      piercing: Some(2),
+      ..shot
+    });
+    game.step_secs(0.5);
+    let healths: Vec<f32> = in_a_line.iter().map(|&target| health(&game, target)).collect();
+    assert_eq!(healths, [9., 9., 10.]);
+    assert!(game.find::<Bullet>().is_none());
 
-    //TODO: spawn hit effect / sound
+    //no hits at all still stops at the first
+    game.send(ShootEvent {
+      piercing: Some(0),
+      ..shot
+    });
+    game.step_secs(0.5);
+    let healths: Vec<f32> = in_a_line.iter().map(|&target| health(&game, target)).collect();
+    assert_eq!(healths, [8., 9., 10.]);
   }
 }
@@ -0,0 +1,316 @@
+//! collider shapes, turned with whatever they're attached to and flattened onto the
+//! x / z play plane, where every part becomes a convex outline with a rounded edge
+use bevy::prelude::*;
+
+///the outline of a `Collider`, in the entity's own space
+#[derive(Clone, Debug)]
+pub enum ColliderShape {
+  Circle(f32),
+  ///a line with a radius around it
+  Capsule {
+    start: Vec3,
+    end: Vec3,
+    radius: f32,
+  },
+  ///a box around the origin, turning with the entity
+  Box(Vec3),
+  ///several shapes, each placed and turned within the entity. like the others it
+  ///doesn't scale
+  Compound(Vec<(Transform, ColliderShape)>),
+}
+
+impl ColliderShape {
+  ///how far from the entity any part of it can reach, for the broad phase
+  pub fn radius(&self) -> f32 {
+    match self {
+      ColliderShape::Circle(radius) => *radius,
+      ColliderShape::Capsule { start, end, radius } => start.length().max(end.length()) + radius,
+      ColliderShape::Box(half_size) => half_size.length(),
+      ColliderShape::Compound(parts) => parts
+        .iter()
+        .map(|(transform, shape)| transform.translation.length() + shape.radius())
+        .fold(0., f32::max),
+    }
+  }
+
+  ///each part as it lies on the play plane
+  pub fn outlines(&self, transform: &GlobalTransform) -> Vec<Outline> {
+    let (_, rotation, translation) = transform.to_scale_rotation_translation();
+    let mut outlines = Vec::new();
+    self.add_outlines(translation, rotation, &mut outlines);
+    outlines
+  }
+
+  fn add_outlines(&self, translation: Vec3, rotation: Quat, outlines: &mut Vec<Outline>) {
+    let point = |local: Vec3| plane(translation + rotation * local);
+    match self {
+      ColliderShape::Circle(radius) => {
+        outlines.push(Outline::new(vec![point(Vec3::ZERO)], *radius))
+      }
+      ColliderShape::Capsule { start, end, radius } => {
+        outlines.push(Outline::new(vec![point(*start), point(*end)], *radius))
+      }
+      ColliderShape::Box(half_size) => {
+        let corners = (0..8)
+          .map(|corner| {
+            let sign = |bit: i32| if corner & bit == 0 { -1. } else { 1. };
+            point(*half_size * Vec3::new(sign(1), sign(2), sign(4)))
+          })
+          .collect();
+        outlines.push(Outline::new(corners, 0.))
+      }
+      ColliderShape::Compound(parts) => {
+        for (part, shape) in parts {
+          let part_translation = translation + rotation * part.translation;
+          shape.add_outlines(part_translation, rotation * part.rotation, outlines);
+        }
+      }
+    }
+  }
+}
+
+pub fn plane(translation: Vec3) -> Vec2 {
+  Vec2::new(translation.x, translation.z)
+}
+
+///a convex outline, anticlockwise, with everything within `radius` of it inside too.
+///one point is a circle, two a capsule
+#[derive(Clone, Debug)]
+pub struct Outline {
+  points: Vec<Vec2>,
+  radius: f32,
+}
+
+impl Outline {
+  pub fn new(points: Vec<Vec2>, radius: f32) -> Self {
+    Self {
+      points: convex_hull(points),
+      radius,
+    }
+  }
+
+  ///how far through a step this first touches `other`, having moved `travel` more than
+  ///it. a circle against anything only needs the other grown by its radius, anything
+  ///else is a point against the outline of every offset where the two overlap
+  pub fn contact(&self, other: &Outline, travel: Vec2) -> Option<f32> {
+    let radius = self.radius + other.radius;
+    match (self.points.as_slice(), other.points.as_slice()) {
+      ([point], _) => other.contact_within(*point - travel, *point, radius),
+      (_, [point]) => self.contact_within(*point + travel, *point, radius),
+      _ => self.difference(other).first_contact(travel, Vec2::ZERO),
+    }
+  }
+
+  //every offset where the two overlap, a point against this is a point against both
+  fn difference(&self, other: &Outline) -> Outline {
+    let points = self
+      .points
+      .iter()
+      .flat_map(|a| other.points.iter().map(move |b| *a - *b))
+      .collect();
+    Outline::new(points, self.radius + other.radius)
+  }
+
+  //how far outside the outline, before the radius, negative when inside it
+  fn distance(&self, point: Vec2) -> f32 {
+    let edges = self.edges();
+    let outside = edges
+      .clone()
+      .map(|(a, b)| distance_to_segment(point, a, b))
+      .fold(f32::MAX, f32::min);
+    let inside = self.points.len() > 2
+      && edges
+        .clone()
+        .all(|(a, b)| (b - a).perp_dot(point - a) >= 0.);
+    if self.points.len() == 1 {
+      point.distance(self.points[0])
+    } else if inside {
+      -outside
+    } else {
+      outside
+    }
+  }
+
+  fn edges(&self) -> impl Iterator<Item = (Vec2, Vec2)> + Clone + '_ {
+    let next = self.points.iter().cycle().skip(1);
+    self.points.iter().copied().zip(next.copied())
+  }
+
+  ///how far along start -> end a point first touches it, 0 to 1
+  pub fn first_contact(&self, start: Vec2, end: Vec2) -> Option<f32> {
+    self.contact_within(start, end, self.radius)
+  }
+
+  //as `first_contact`, with the outline grown by `radius` instead of its own
+  fn contact_within(&self, start: Vec2, end: Vec2, radius: f32) -> Option<f32> {
+    if self.distance(start) < radius {
+      return Some(0.);
+    }
+    //coming in from outside, it first crosses either a rounded corner or a pushed out edge
+    let corners = self
+      .points
+      .iter()
+      .filter_map(|point| circle_contact(start, end, *point, radius));
+    let edges = self.edges().filter(|(a, b)| a != b).filter_map(|(a, b)| {
+      let out = (b - a).perp().normalize() * -radius;
+      segment_contact(start, end, a + out, b + out)
+    });
+    corners.chain(edges).min_by(f32::total_cmp)
+  }
+}
+
+//anticlockwise, with anything on an edge dropped
+fn convex_hull(mut points: Vec<Vec2>) -> Vec<Vec2> {
+  points.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+  points.dedup();
+  if points.len() < 3 {
+    return points;
+  }
+  //andrew's monotone chain, lower half then upper half
+  let mut hull: Vec<Vec2> = Vec::with_capacity(points.len() * 2);
+  for pass in [points.clone(), points.into_iter().rev().collect()] {
+    let start = hull.len();
+    for point in pass {
+      while hull.len() >= start + 2 {
+        let a = hull[hull.len() - 2];
+        let b = hull[hull.len() - 1];
+        if (b - a).perp_dot(point - a) > 0. {
+          break;
+        }
+        hull.pop();
+      }
+      hull.push(point);
+    }
+    hull.pop();
+  }
+  hull
+}
+
+fn distance_to_segment(point: Vec2, a: Vec2, b: Vec2) -> f32 {
+  let along = b - a;
+  let length_squared = along.length_squared();
+  if length_squared == 0. {
+    return point.distance(a);
+  }
+  let t = ((point - a).dot(along) / length_squared).clamp(0., 1.);
+  point.distance(a + along * t)
+}
+
+//how far along start -> end it first comes within radius of the centre
+fn circle_contact(start: Vec2, end: Vec2, centre: Vec2, radius: f32) -> Option<f32> {
+  let offset = start - centre;
+  let c = offset.length_squared() - radius * radius;
+  if c < 0. {
+    return Some(0.);
+  }
+  let travel = end - start;
+  let a = travel.length_squared();
+  let b = offset.dot(travel);
+  //not moving, or moving away
+  if a == 0. || b >= 0. {
+    return None;
+  }
+  let discriminant = b * b - a * c;
+  if discriminant < 0. {
+    return None;
+  }
+  let t = (-b - discriminant.sqrt()) / a;
+  (t <= 1.).then_some(t)
+}
+
+//how far along start -> end it crosses a -> b
+fn segment_contact(start: Vec2, end: Vec2, a: Vec2, b: Vec2) -> Option<f32> {
+  let travel = end - start;
+  let edge = b - a;
+  let denominator = travel.perp_dot(edge);
+  if denominator == 0. {
+    return None;
+  }
+  let t = (a - start).perp_dot(edge) / denominator;
+  let s = (a - start).perp_dot(travel) / denominator;
+  ((0. ..=1.).contains(&t) && (0. ..=1.).contains(&s)).then_some(t)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::f32::consts::FRAC_PI_2;
+
+  #[test]
+  fn shapes_turn_with_what_they_are_on() {
+    let capsule = ColliderShape::Capsule {
+      start: Vec3::new(-3., 0., 0.),
+      end: Vec3::new(3., 0., 0.),
+      radius: 1.,
+    };
+    let touches = |transform: Transform, point: Vec2| {
+      capsule
+        .outlines(&GlobalTransform::from(transform))
+        .iter()
+        .any(|outline| outline.first_contact(point, point).is_some())
+    };
+    let along_x = Vec2::new(3.5, 0.);
+    let along_z = Vec2::new(0., 3.5);
+    assert!(touches(Transform::IDENTITY, along_x));
+    assert!(!touches(Transform::IDENTITY, along_z));
+
+    let turned = Transform::from_rotation(Quat::from_rotation_y(FRAC_PI_2));
+    assert!(!touches(turned, along_x));
+    assert!(touches(turned, along_z));
+
+    //standing on its end it's just a circle from above
+    let rolled = Transform::from_rotation(Quat::from_rotation_z(FRAC_PI_2));
+    assert!(!touches(rolled, along_x));
+    assert!(touches(rolled, Vec2::new(0.9, 0.)));
+  }
+
+  #[test]
+  fn a_point_crossing_a_box_stops_at_its_near_side() {
+    let square = Outline::new(
+      vec![
+        Vec2::new(-1., -1.),
+        Vec2::new(1., -1.),
+        Vec2::new(1., 1.),
+        Vec2::new(-1., 1.),
+      ],
+      0.,
+    );
+    let t = square
+      .first_contact(Vec2::new(-5., 0.), Vec2::new(5., 0.))
+      .expect("crosses it");
+    assert!((t - 0.4).abs() < 1e-5);
+    assert_eq!(
+      square.first_contact(Vec2::new(0.5, 0.), Vec2::new(0.5, 0.)),
+      Some(0.)
+    );
+    assert_eq!(
+      square.first_contact(Vec2::new(-5., 2.), Vec2::new(5., 2.)),
+      None
+    );
+  }
+
+  #[test]
+  fn circles_take_the_short_cut_to_the_same_answer() {
+    let square = Outline::new(
+      vec![
+        Vec2::new(4., -1.),
+        Vec2::new(6., -1.),
+        Vec2::new(6., 1.),
+        Vec2::new(4., 1.),
+      ],
+      0.5,
+    );
+    let circle = Outline::new(vec![Vec2::new(-1., 0.5)], 1.);
+    for travel in [Vec2::new(10., 0.), Vec2::new(8., 3.), Vec2::new(0., 4.)] {
+      let long_way = circle.difference(&square).first_contact(travel, Vec2::ZERO);
+      let short_cut = circle.contact(&square, travel);
+      assert_eq!(long_way.is_some(), short_cut.is_some());
+      if let (Some(a), Some(b)) = (long_way, short_cut) {
+        assert!((a - b).abs() < 1e-4);
+      }
+      //and the other way round
+      let back = square.contact(&circle, -travel);
+      assert_eq!(short_cut.is_some(), back.is_some());
+    }
+  }
+}
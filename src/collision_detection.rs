@@ -1,11 +1,12 @@
 use bevy::prelude::*;
 
 use crate::{
-  bullet::{Bullet, BulletHitEvent},
-  health::HealthEvent,
-  hook::Hook,
+  bullet::{Bullet, BulletHitEvent, Piercing},
+  collider_shape::{plane, ColliderShape, Outline},
+  health::{DamageSource, HealthEvent},
   scheduling::GameSchedule,
-  ship::Invincible,
+  ship::PlayerNumber,
+  spatial_grid::SpatialGrid,
 };
 
 pub struct CollsionDetectionPlugin;
@@ -13,12 +14,15 @@ pub struct CollsionDetectionPlugin;
 impl Plugin for CollsionDetectionPlugin {
   fn build(&self, app: &mut App) {
     app
+      .init_resource::<SpatialGrid>()
+      .init_resource::<CollisionMatrix>()
       .add_systems(
-        PostUpdate,
+        FixedUpdate,
         (
-          player_bullet_collision_detection,
-          enemy_bullet_collision_detection,
-          player_collision_detection,
+          update_spatial_grid,
+          projectile_collision_detection,
+          collider_collision_detection,
+          update_swept,
         )
           .chain()
           .in_set(GameSchedule::CollisionDetection),
@@ -30,32 +34,135 @@ impl Plugin for CollsionDetectionPlugin {
 
 #[derive(Component)]
 pub struct Collider {
-  pub radius: f32,
+  pub shape: ColliderShape,
   pub collision_damage: f32,
+  //where the shape lies this step, worked out once for every check to share
+  outlines: Vec<Outline>,
 }
 
 impl Collider {
   pub fn new(radius: f32, collision_damage: f32) -> Self {
+    Self::shaped(ColliderShape::Circle(radius), collision_damage)
+  }
+
+  pub fn shaped(shape: ColliderShape, collision_damage: f32) -> Self {
     Self {
-      radius,
+      shape,
       collision_damage,
+      outlines: Vec::new(),
     }
   }
 }
 
+///checked along the line it moved since the last step as well as where it ended up,
+///so nothing fast skips over what's in its way. only the first thing it meets counts
 #[derive(Component, Default)]
-pub struct Player;
+pub struct Swept {
+  //where the last step's checks left it, none until it's been through one
+  last: Option<Vec3>,
+}
+
+///what something is as far as collisions go, what it hits is up to the `CollisionMatrix`
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum CollisionLayer {
+  Player,
+  PlayerProjectile,
+  Hook,
+  ///a captured enemy, fighting on the side of whoever caught it
+  Captive,
+  Enemy,
+  EnemyProjectile,
+  Pickup,
+  ///scenery, or anything that's out of the fight for now
+  #[default]
+  Neutral,
+}
+
+impl CollisionLayer {
+  fn bit(self) -> u8 {
+    1 << self as u8
+  }
+
+  //who gets the credit when something on this layer does the damage
+  fn damage_source(self, player: usize) -> DamageSource {
+    match self {
+      CollisionLayer::Player | CollisionLayer::Hook => DamageSource::ShipRam(player),
+      CollisionLayer::Captive => DamageSource::CaptiveRam(player),
+      _ => DamageSource::Enemy,
+    }
+  }
+}
+
+///a set of layers
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct LayerMask(u8);
+
+impl LayerMask {
+  pub fn contains(&self, layer: CollisionLayer) -> bool {
+    self.0 & layer.bit() != 0
+  }
+}
+
+///which layers hit which. colliders that hit each other trade collision damage
+///and send a `CollisionEvent` with the one doing the hitting first,
+///projectiles just damage what they hit
+#[derive(Resource)]
+pub struct CollisionMatrix {
+  masks: [LayerMask; 8],
+}
+
+impl Default for CollisionMatrix {
+  fn default() -> Self {
+    use CollisionLayer::*;
+    let mut matrix = Self {
+      masks: [LayerMask::default(); 8],
+    };
+    for (layer, others) in [
+      (Player, &[Enemy, Pickup][..]),
+      (PlayerProjectile, &[Enemy]),
+      (Hook, &[Enemy]),
+      (Captive, &[Enemy]),
+      (EnemyProjectile, &[Player, Captive]),
+    ] {
+      for &other in others {
+        matrix.set(layer, other, true);
+      }
+    }
+    matrix
+  }
+}
+
+impl CollisionMatrix {
+  ///everything a layer hits
+  pub fn mask(&self, layer: CollisionLayer) -> LayerMask {
+    self.masks[layer as usize]
+  }
+
+  pub fn hits(&self, layer: CollisionLayer, other: CollisionLayer) -> bool {
+    self.mask(layer).contains(other)
+  }
+
+  pub fn set(&mut self, layer: CollisionLayer, other: CollisionLayer, hits: bool) {
+    let mask = &mut self.masks[layer as usize];
+    if hits {
+      mask.0 |= other.bit();
+    } else {
+      mask.0 &= !other.bit();
+    }
+  }
+}
 
 #[derive(Event)]
 pub struct CollisionEvent {
-  pub player: Entity,
+  ///the one whose layer hits the other's
+  pub hitter: Entity,
   pub other: Entity,
 }
 
 impl CollisionEvent {
   pub fn new(entity: Entity, collided: Entity) -> Self {
     Self {
-      player: entity,
+      hitter: entity,
       other: collided,
     }
   }
@@ -73,66 +180,154 @@ impl BulletCollisionEvent {
   }
 }
  */
-fn player_bullet_collision_detection(
-  mut ev_health_writer: EventWriter<HealthEvent>,
-  mut ev_bullet_hit_writer: EventWriter<BulletHitEvent>,
-  bullet_query: Query<(Entity, &GlobalTransform, &Bullet), With<Player>>,
-  target_query: Query<(Entity, &GlobalTransform, &Collider), Without<Player>>,
+//start and end of the line something covered this step, just a point if it isn't swept
+fn path(transform: &GlobalTransform, swept: Option<&Swept>) -> (Vec3, Vec3) {
+  let end = transform.translation();
+  (swept.and_then(|swept| swept.last).unwrap_or(end), end)
+}
+
+//how far through the step two sets of outlines first touch, given how far the first
+//moved compared to the second
+fn first_contact(outlines: &[Outline], other_outlines: &[Outline], travel: Vec3) -> Option<f32> {
+  outlines
+    .iter()
+    .flat_map(|outline| {
+      other_outlines
+        .iter()
+        .filter_map(move |other| outline.contact(other, plane(travel)))
+    })
+    .min_by(f32::total_cmp)
+}
+
+//every collider goes in the grid once a step with its outlines worked out, all the
+//checks below share them. swept ones cover the whole line they moved along
+fn update_spatial_grid(
+  mut grid: ResMut<SpatialGrid>,
+  mut query: Query<(Entity, &GlobalTransform, &mut Collider, Option<&Swept>)>,
 ) {
-  for (bullet_entity, bullet_transform, bullet) in bullet_query.iter() {
-    for (target_entity, tagret_transform, collider) in target_query.iter() {
-      let dist_sqr = bullet_transform
-        .translation()
-        .distance_squared(tagret_transform.translation());
-      if dist_sqr < collider.radius * collider.radius {
-        ev_health_writer.write(HealthEvent::new(target_entity, bullet.damage));
-        ev_bullet_hit_writer.write(BulletHitEvent::new(bullet_entity, Some(target_entity)));
-      }
-    }
+  grid.clear();
+  for (entity, transform, mut collider, swept) in query.iter_mut() {
+    collider.outlines = collider.shape.outlines(transform);
+    let (start, end) = path(transform, swept);
+    grid.insert(
+      entity,
+      start.midpoint(end),
+      collider.shape.radius() + start.distance(end) * 0.5,
+    );
+  }
+}
+
+//remember where everything swept finished up, ready for the next step
+fn update_swept(mut query: Query<(&mut Swept, &GlobalTransform)>) {
+  for (mut swept, transform) in query.iter_mut() {
+    swept.last = Some(transform.translation());
   }
 }
 
-fn enemy_bullet_collision_detection(
+//a bullet hits the first thing along its way, or the nearest if it starts out touching
+//more than one. piercing ones carry on to as many as they have hits left
+fn projectile_collision_detection(
   mut ev_health_writer: EventWriter<HealthEvent>,
   mut ev_bullet_hit_writer: EventWriter<BulletHitEvent>,
-  bullet_query: Query<(Entity, &GlobalTransform, &Bullet), Without<Player>>,
-  target_query: Query<
-    (Entity, &GlobalTransform, &Collider),
-    (With<Player>, Without<Hook>, Without<Invincible>),
-  >,
+  grid: Res<SpatialGrid>,
+  matrix: Res<CollisionMatrix>,
+  bullet_query: Query<(Entity, &GlobalTransform, &Bullet, &CollisionLayer, Option<&Piercing>)>,
+  target_query: Query<(&GlobalTransform, &Collider, &CollisionLayer)>,
+  swept_query: Query<&Swept>,
 ) {
-  for (target_entity, tagret_transform, collider) in target_query.iter() {
-    for (bullet_entity, bullet_transform, bullet) in bullet_query.iter() {
-      let dist_sqr = bullet_transform
-        .translation()
-        .distance_squared(tagret_transform.translation());
-      if dist_sqr < collider.radius * collider.radius {
-        info!("hit ent {:?}", target_entity);
-        ev_health_writer.write(HealthEvent::new(target_entity, bullet.damage));
-        ev_bullet_hit_writer.write(BulletHitEvent::new(bullet_entity, Some(target_entity)));
+  for (bullet_entity, bullet_transform, bullet, bullet_layer, piercing) in bullet_query.iter() {
+    let (start, end) = path(bullet_transform, swept_query.get(bullet_entity).ok());
+    let mut contacts = Vec::new();
+    for target_entity in grid.nearby(start.midpoint(end), start.distance(end) * 0.5) {
+      let Ok((target_transform, collider, layer)) = target_query.get(target_entity) else {
+        continue;
+      };
+      if !matrix.hits(*bullet_layer, *layer)
+        || piercing.is_some_and(|piercing| piercing.has_hit(target_entity))
+      {
+        continue;
       }
+      //measured from the target, so it can be moving too
+      let (target_start, target_end) = path(target_transform, swept_query.get(target_entity).ok());
+      let travel = (end - start) - (target_end - target_start);
+      //a point needs nothing more than the target's outlines
+      let contact = collider
+        .outlines
+        .iter()
+        .filter_map(|outline| outline.first_contact(plane(end - travel), plane(end)))
+        .min_by(f32::total_cmp);
+      if let Some(t) = contact {
+        let distance = start.distance_squared(target_start);
+        contacts.push((t, distance, target_entity));
+      }
+    }
+    contacts.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)));
+
+    let hits = piercing.map_or(1, |piercing| piercing.hits_remaining as usize);
+    for &(_, _, target_entity) in contacts.iter().take(hits) {
+      ev_health_writer.write(HealthEvent::new(target_entity, bullet.damage, bullet.source));
+      ev_bullet_hit_writer.write(BulletHitEvent::new(bullet_entity, Some(target_entity)));
     }
   }
 }
 
-fn player_collision_detection(
+fn collider_collision_detection(
   mut ev_health_writer: EventWriter<HealthEvent>,
   mut ev_collision_writer: EventWriter<CollisionEvent>,
-  player_query: Query<(Entity, &GlobalTransform, &Collider), (With<Player>, Without<Invincible>)>,
-  enemy_query: Query<(Entity, &GlobalTransform, &Collider), Without<Player>>,
+  grid: Res<SpatialGrid>,
+  matrix: Res<CollisionMatrix>,
+  query: Query<(
+    Entity,
+    &GlobalTransform,
+    &Collider,
+    &CollisionLayer,
+    Option<&PlayerNumber>,
+  )>,
+  swept_query: Query<&Swept>,
+  mut contacts: Local<Vec<(f32, Entity)>>,
 ) {
-  for (player, player_transform, player_collider) in player_query.iter() {
-    for (enemy, enemy_transform, enemy_collider) in enemy_query.iter() {
-      let dist_sqr = player_transform
-        .translation()
-        .distance_squared(enemy_transform.translation());
-      let collision_seperation = player_collider.radius + enemy_collider.radius;
-      if dist_sqr < collision_seperation * collision_seperation {
-        ev_health_writer.write(HealthEvent::new(player, enemy_collider.collision_damage));
-        ev_health_writer.write(HealthEvent::new(enemy, player_collider.collision_damage));
-        ev_collision_writer.write(CollisionEvent::new(player, enemy));
+  let player = |number: Option<&PlayerNumber>| number.map(|number| number.0).unwrap_or_default();
+  for (entity, transform, collider, layer, number) in query.iter() {
+    let mask = matrix.mask(*layer);
+    if mask == LayerMask::default() {
+      continue;
+    }
+    let swept = swept_query.get(entity).ok();
+    let (start, end) = path(transform, swept);
+    contacts.clear();
+    let reach = collider.shape.radius() + start.distance(end) * 0.5;
+    for other in grid.nearby(start.midpoint(end), reach) {
+      let Ok((_, other_transform, other_collider, other_layer, _)) = query.get(other) else {
+        continue;
+      };
+      if other == entity || !mask.contains(*other_layer) {
+        continue;
+      }
+      //measured from the other one, so either or both can be swept
+      let (other_start, other_end) = path(other_transform, swept_query.get(other).ok());
+      let travel = (end - start) - (other_end - other_start);
+      if let Some(t) = first_contact(&collider.outlines, &other_collider.outlines, travel) {
+        contacts.push((t, other));
       }
     }
+    if swept.is_some() {
+      contacts.sort_by(|a, b| a.0.total_cmp(&b.0));
+      contacts.truncate(1);
+    }
+
+    let source = layer.damage_source(player(number));
+    for &(_, other) in contacts.iter() {
+      let Ok((_, _, other_collider, other_layer, other_number)) = query.get(other) else {
+        continue;
+      };
+      ev_health_writer.write(HealthEvent::new(
+        entity,
+        other_collider.collision_damage,
+        other_layer.damage_source(player(other_number)),
+      ));
+      ev_health_writer.write(HealthEvent::new(other, collider.collision_damage, source));
+      ev_collision_writer.write(CollisionEvent::new(entity, other));
+    }
   }
 }
 
@@ -179,3 +374,67 @@ fn apply_bullet_collisions(
   }
 }
  */
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{bullet::ShootEvent, headless::HeadlessApp, health::Health, ship::PlayerShip};
+
+  #[test]
+  fn matrix_can_be_changed_for_new_kinds_of_thing() {
+    let mut matrix = CollisionMatrix::default();
+    assert!(matrix.hits(CollisionLayer::EnemyProjectile, CollisionLayer::Captive));
+    assert!(!matrix.hits(CollisionLayer::EnemyProjectile, CollisionLayer::Hook));
+    assert!(!matrix.hits(CollisionLayer::Neutral, CollisionLayer::Player));
+
+    //a hazard that hurts everyone
+    matrix.set(CollisionLayer::Neutral, CollisionLayer::Player, true);
+    matrix.set(CollisionLayer::Neutral, CollisionLayer::Enemy, true);
+    assert!(matrix.hits(CollisionLayer::Neutral, CollisionLayer::Player));
+    assert!(matrix.hits(CollisionLayer::Neutral, CollisionLayer::Enemy));
+    matrix.set(CollisionLayer::Neutral, CollisionLayer::Player, false);
+    assert!(!matrix.hits(CollisionLayer::Neutral, CollisionLayer::Player));
+  }
+
+  #[test]
+  fn enemy_shots_pass_through_a_new_ship_until_it_joins_the_fight() {
+    let mut game = HeadlessApp::new();
+    let ship = game.find::<PlayerShip>().expect("ship spawned");
+    let at = game.world().get::<GlobalTransform>(ship).unwrap().translation();
+    let shoot = || ShootEvent::new(DamageSource::Enemy, at, Vec3::ZERO, -20., 1.);
+
+    game.send(shoot());
+    game.step_frames(3);
+    assert_eq!(game.world().get::<Health>(ship).unwrap().value, 100.);
+    assert_eq!(
+      game.world().get::<CollisionLayer>(ship),
+      Some(&CollisionLayer::Neutral)
+    );
+
+    game.step_secs(2.);
+    assert_eq!(
+      game.world().get::<CollisionLayer>(ship),
+      Some(&CollisionLayer::Player)
+    );
+    game.send(shoot());
+    game.step_frames(3);
+    assert!(game.world().get::<Health>(ship).unwrap().value < 100.);
+  }
+
+  #[test]
+  fn fast_shots_hit_the_first_thing_on_their_way_instead_of_skipping_it() {
+    let mut game = HeadlessApp::new();
+    let near = game.spawn_target(Vec3::new(0., 0., -30.), 1.6, 10.);
+    let far = game.spawn_target(Vec3::new(4., 0., -30.), 1.6, 10.);
+
+    //20 a step, from -10 straight to 10 without ever landing on either
+    let velocity = Vec3::new(1200., 0., 0.);
+    let start = Vec3::new(-50., 0., -30.);
+    game.send(ShootEvent::new(DamageSource::ShipRam(0), start, velocity, -1., 1.));
+    game.step_frames(6);
+
+    let health = |game: &HeadlessApp, entity| game.world().get::<Health>(entity).unwrap().value;
+    assert_eq!(health(&game, near), 9.);
+    assert_eq!(health(&game, far), 10.);
+  }
+}
@@ -0,0 +1,105 @@
+use bevy::prelude::*;
+
+use crate::{
+  game_manager::Game,
+  health::{DamageSource, HealthEvent},
+  scheduling::GameSchedule,
+  ship::PlayerShip,
+};
+
+//time allowed between kills before the chain breaks
+const COMBO_WINDOW_SECONDS: f32 = 3.0;
+const COMBO_KILLS_PER_MULTIPLIER: u32 = 3;
+const COMBO_MAX_MULTIPLIER: u64 = 8;
+const COMBO_CAPTIVE_SHOT_BONUS: u64 = 50;
+const COMBO_CAPTIVE_RAM_BONUS: u64 = 150;
+
+pub struct ComboPlugin;
+
+impl Plugin for ComboPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .add_systems(
+        FixedUpdate,
+        break_combo_on_hit.in_set(GameSchedule::HealthAdjust),
+      )
+      .add_systems(FixedUpdate, tick_combo.in_set(GameSchedule::EntityUpdates));
+  }
+}
+
+///kill chain, lives alongside the game
+#[derive(Component)]
+pub struct Combo {
+  chain: u32,
+  timer: Timer,
+}
+
+impl Default for Combo {
+  fn default() -> Self {
+    Self {
+      chain: 0,
+      timer: Timer::from_seconds(COMBO_WINDOW_SECONDS, TimerMode::Once),
+    }
+  }
+}
+
+impl Combo {
+  pub fn multiplier(&self) -> u64 {
+    (1 + (self.chain / COMBO_KILLS_PER_MULTIPLIER) as u64).min(COMBO_MAX_MULTIPLIER)
+  }
+
+  ///how much of the window is left to keep the chain going, 0 when there's no chain
+  pub fn time_remaining(&self) -> f32 {
+    if self.chain == 0 {
+      return 0.;
+    }
+    self.timer.fraction_remaining()
+  }
+
+  ///score for a kill, player kills extend the chain and earn bonuses for using captives
+  pub fn award(&mut self, points: u64, source: DamageSource) -> u64 {
+    if !source.is_player() {
+      return points;
+    }
+    let bonus = match source {
+      DamageSource::CaptiveShot(_) => COMBO_CAPTIVE_SHOT_BONUS,
+      DamageSource::CaptiveRam(_) => COMBO_CAPTIVE_RAM_BONUS,
+      _ => 0,
+    };
+    let total = (points + bonus) * self.multiplier();
+    self.chain += 1;
+    self.timer.reset();
+    total
+  }
+
+  pub fn reset(&mut self) {
+    self.chain = 0;
+  }
+}
+
+fn tick_combo(mut combo: Single<&mut Combo, With<Game>>, time: Res<Time>) {
+  if combo.chain == 0 {
+    return;
+  }
+  combo.timer.tick(time.delta());
+  if combo.timer.just_finished() {
+    combo.reset();
+  }
+}
+
+fn break_combo_on_hit(
+  mut ev_health_reader: EventReader<HealthEvent>,
+  mut combo: Single<&mut Combo, With<Game>>,
+  player_query: Query<(), With<PlayerShip>>,
+) {
+  for HealthEvent {
+    entity,
+    health_adjustment,
+    ..
+  } in ev_health_reader.read()
+  {
+    if *health_adjustment < 0. && player_query.contains(*entity) {
+      combo.reset();
+    }
+  }
+}
@@ -0,0 +1,178 @@
+use bevy::prelude::*;
+
+use crate::{
+  asset_loader::SceneAssets,
+  bindings::{Binding, Bindings, ControlAction, Rebinding},
+  menu::{
+    menu_heading, menu_item, menu_root, MenuAction, MenuActionEvent, MenuAdjustEvent, MenuItem,
+    MenuPage,
+  },
+  scheduling::GameSchedule,
+};
+
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .add_systems(OnEnter(MenuPage::Controls), spawn_controls_page)
+      .add_systems(OnExit(MenuPage::Controls), cancel_rebinding)
+      .add_systems(
+        Update,
+        (capture_binding, controls_actions, update_control_labels)
+          .chain()
+          //after input's been read, so the button that gets bound isn't also read as a press
+          .after(GameSchedule::ReadInput)
+          .run_if(in_state(MenuPage::Controls)),
+      );
+  }
+}
+
+//current text for controls entries, None for plain menu entries
+fn control_label(action: MenuAction, bindings: &Bindings, rebinding: &Rebinding) -> Option<String> {
+  match action {
+    MenuAction::Rebind(control) if rebinding.0 == Some(control) => {
+      Some(format!("{}: press a button", control.name()))
+    }
+    MenuAction::Rebind(control) => Some(format!("{}: {}", control.name(), bindings.label(control))),
+    MenuAction::KeyboardLayout => Some(format!("Keyboard: {}", bindings.layout.name())),
+    MenuAction::StickDeadzone => Some(format!(
+      "Stick deadzone: {}%",
+      (bindings.stick.deadzone * 100.).round()
+    )),
+    MenuAction::StickResponse => Some(format!(
+      "Stick response: {}",
+      bindings.stick.response.name()
+    )),
+    _ => None,
+  }
+}
+
+fn spawn_controls_page(
+  mut commands: Commands,
+  scene_assets: Res<SceneAssets>,
+  bindings: Res<Bindings>,
+  rebinding: Res<Rebinding>,
+) {
+  let font = scene_assets.font.clone();
+  let entries = ControlAction::ALL
+    .into_iter()
+    .map(MenuAction::Rebind)
+    .chain([
+      MenuAction::KeyboardLayout,
+      MenuAction::StickDeadzone,
+      MenuAction::StickResponse,
+      MenuAction::ResetControls,
+      MenuAction::Back,
+    ]);
+  commands
+    .spawn((StateScoped(MenuPage::Controls), menu_root()))
+    .with_children(|parent| {
+      parent.spawn(menu_heading("Controls", font.clone()));
+      for (index, action) in entries.enumerate() {
+        let label = control_label(action, &bindings, &rebinding).unwrap_or_else(|| {
+          match action {
+            MenuAction::ResetControls => "Reset to defaults",
+            _ => "Back",
+          }
+          .into()
+        });
+        parent.spawn(menu_item(index, &label, action, font.clone()));
+      }
+    });
+}
+
+fn controls_actions(
+  mut ev_menu_action_reader: EventReader<MenuActionEvent>,
+  mut ev_menu_adjust_reader: EventReader<MenuAdjustEvent>,
+  mut bindings: ResMut<Bindings>,
+  mut rebinding: ResMut<Rebinding>,
+) {
+  //left and right nudge the deadzone, selecting it steps up and wraps round
+  for MenuAdjustEvent { action, step } in ev_menu_adjust_reader.read() {
+    if *action == MenuAction::StickDeadzone {
+      bindings.stick.step_deadzone(*step);
+      bindings.save();
+    }
+  }
+  for MenuActionEvent(action) in ev_menu_action_reader.read() {
+    match action {
+      MenuAction::Rebind(control) => rebinding.0 = Some(*control),
+      MenuAction::KeyboardLayout => {
+        bindings.layout = bindings.layout.next();
+        bindings.save();
+      }
+      MenuAction::StickDeadzone => {
+        bindings.stick.cycle_deadzone();
+        bindings.save();
+      }
+      MenuAction::StickResponse => {
+        bindings.stick.response = bindings.stick.response.next();
+        bindings.save();
+      }
+      MenuAction::ResetControls => {
+        bindings.reset();
+        bindings.save();
+      }
+      _ => {}
+    }
+  }
+}
+
+//next button pressed on anything gets bound, escape gives up
+fn capture_binding(
+  keys: Res<ButtonInput<KeyCode>>,
+  mouse: Res<ButtonInput<MouseButton>>,
+  gamepads: Query<&Gamepad>,
+  mut bindings: ResMut<Bindings>,
+  mut rebinding: ResMut<Rebinding>,
+) {
+  let Some(control) = rebinding.0 else {
+    return;
+  };
+  if keys.just_pressed(KeyCode::Escape) {
+    rebinding.0 = None;
+    return;
+  }
+  //left button is for dragging the ship and clicking the menus
+  let pressed = keys
+    .get_just_pressed()
+    .map(|key| Binding::Key(*key))
+    .chain(
+      mouse
+        .get_just_pressed()
+        .filter(|button| **button != MouseButton::Left)
+        .map(|button| Binding::Mouse(*button)),
+    )
+    .chain(
+      gamepads
+        .iter()
+        .flat_map(|gamepad| gamepad.get_just_pressed())
+        .map(|button| Binding::Gamepad(*button)),
+    )
+    .next();
+  if let Some(binding) = pressed {
+    bindings.rebind(control, binding);
+    bindings.save();
+    rebinding.0 = None;
+  }
+}
+
+fn cancel_rebinding(mut rebinding: ResMut<Rebinding>) {
+  rebinding.0 = None;
+}
+
+fn update_control_labels(
+  mut query: Query<(&MenuItem, &mut Text)>,
+  bindings: Res<Bindings>,
+  rebinding: Res<Rebinding>,
+) {
+  if !bindings.is_changed() && !rebinding.is_changed() {
+    return;
+  }
+  for (item, mut text) in query.iter_mut() {
+    if let Some(label) = control_label(item.action, &bindings, &rebinding) {
+      text.0 = label;
+    }
+  }
+}
@@ -0,0 +1,140 @@
+use bevy::prelude::*;
+
+use crate::{
+  effect_sprite::{EffectSpriteEvent, EffectSpriteType},
+  enemy::{EnemyKind, SpawnEnemyEvent},
+  health::{DamageSource, Health},
+  movement::Velocity,
+  scheduling::GameSchedule,
+  wreck::WreckedEvent,
+};
+
+//gap along z between things dropped on death
+const DEATH_DROP_SPACING: f32 = 4.0;
+
+pub struct DeathPlugin;
+
+impl Plugin for DeathPlugin {
+  fn build(&self, app: &mut App) {
+    app.add_systems(FixedUpdate, check_dead.in_set(GameSchedule::DespawnEntities));
+  }
+}
+
+///what's left behind when something is destroyed
+#[derive(Clone)]
+pub enum Remains {
+  ///just a flash, nothing left over
+  Effect(EffectSpriteType),
+  ///burning hulk that tumbles away before blowing up
+  Wreck {
+    scene: Handle<Scene>,
+    roll_speed: f32,
+    time_to_live: f32,
+  },
+}
+
+///despawned once health runs out, leaving remains and drops behind and scoring its points
+#[derive(Component, Clone)]
+pub struct Destroyable {
+  pub remains: Remains,
+  pub blast_size: f32,
+  pub points: u64,
+  ///enemies released on death
+  pub drops: Vec<EnemyKind>,
+}
+
+impl Destroyable {
+  pub fn new(remains: Remains, blast_size: f32, points: u64) -> Self {
+    Self {
+      remains,
+      blast_size,
+      points,
+      drops: Vec::new(),
+    }
+  }
+
+  pub fn with_drops(mut self, drops: Vec<EnemyKind>) -> Self {
+    self.drops = drops;
+    self
+  }
+}
+
+///triggered on the dying entity just before it's despawned, so observers can still query it
+#[derive(Event, Clone)]
+pub struct DeathEvent {
+  pub translation: Vec3,
+  pub points: u64,
+  ///who gets the credit
+  pub source: DamageSource,
+}
+
+impl DeathEvent {
+  pub fn new(translation: Vec3, points: u64, source: DamageSource) -> Self {
+    Self {
+      translation,
+      points,
+      source,
+    }
+  }
+}
+
+fn check_dead(
+  mut commands: Commands,
+  query: Query<(
+    Entity,
+    &Health,
+    &Destroyable,
+    &GlobalTransform,
+    Option<&Velocity>,
+  )>,
+  mut ev_wreck_writer: EventWriter<WreckedEvent>,
+  mut ev_effect_writer: EventWriter<EffectSpriteEvent>,
+  mut ev_spawn_enemy_writer: EventWriter<SpawnEnemyEvent>,
+) {
+  for (entity, health, destroyable, transform, velocity) in query.iter() {
+    if health.value > 0. {
+      continue;
+    }
+    info!("dead {:?}", entity);
+    let translation = transform.translation();
+    //targets and the like never move, so they go off where they stand
+    let velocity = velocity.map_or(Vec3::ZERO, |velocity| velocity.0);
+    match &destroyable.remains {
+      Remains::Effect(effect) => {
+        ev_effect_writer.write(EffectSpriteEvent::new(
+          translation,
+          destroyable.blast_size,
+          velocity,
+          *effect,
+        ));
+      }
+      Remains::Wreck {
+        scene,
+        roll_speed,
+        time_to_live,
+      } => {
+        ev_wreck_writer.write(WreckedEvent::new(
+          scene.clone(),
+          translation,
+          transform.rotation(),
+          velocity,
+          *roll_speed,
+          *time_to_live,
+          destroyable.blast_size,
+        ));
+      }
+    }
+
+    let mid = (destroyable.drops.len() as f32 - 1.) * 0.5;
+    for (i, kind) in destroyable.drops.iter().enumerate() {
+      let offset = Vec3::new(0., 0., (i as f32 - mid) * DEATH_DROP_SPACING);
+      ev_spawn_enemy_writer.write(SpawnEnemyEvent::new(*kind, translation + offset, None));
+    }
+
+    commands.trigger_targets(
+      DeathEvent::new(translation, destroyable.points, health.last_hit),
+      entity,
+    );
+    commands.entity(entity).despawn();
+  }
+}
@@ -1,17 +1,248 @@
 use bevy::prelude::*;
+use std::{f32::consts::PI, time::Duration};
 
+use crate::{
+  ai::AiConfigCollection,
+  asset_loader::SceneAssets,
+  bounds_check::BoundsDespawn,
+  projectile::ProjectileKind,
+  collider_shape::ColliderShape,
+  collision_detection::Collider,
+  death::{Destroyable, Remains},
+  enemy::{Enemy, EnemyKind, SpawnEnemyEvent},
+  health::Health,
+  hit_marker::HitMarker,
+  hook::{Heavy, Hookable, Hooked},
+  movement::{Acceleration, Velocity},
+  scheduling::GameSchedule,
+  ship::Jettisoned,
+  weapon::{Weapon, WeaponProfile},
+};
 
+const DROPSHIP_SPEED: f32 = 6.0;
+const DROPSHIP_MAX_SPEED: f32 = 10.0;
+//the long hull, and the engine pods either side of it along y
+const DROPSHIP_HULL_OFFSET: Vec3 = Vec3::new(1.5, 0., 0.);
+const DROPSHIP_HULL_SIZE: Vec3 = Vec3::new(4.2, 2.1, 1.4);
+const DROPSHIP_ENGINE_OFFSET: Vec3 = Vec3::new(1.0, 3.2, 0.5);
+const DROPSHIP_ENGINE_SIZE: Vec3 = Vec3::new(1.6, 1.4, 0.5);
+const DROPSHIP_COLLISION_DAMAGE: f32 = -60.0;
+const DROPSHIP_HEALTH: f32 = 400.0;
+//held out in front of the claw by its tail, and a drag on whoever's holding it
+const DROPSHIP_HOOK_TRANSLATION: Vec3 = Vec3::new(-6.2, 0., 0.);
+const DROPSHIP_HOOK_ROTATION: f32 = 0.0;
+const DROPSHIP_CAPTOR_SPEED: f32 = 0.5;
+//holds its fire in the wild, once captured it's a beam
+const DROPSHIP_CAPTURED_WEAPON: WeaponProfile = WeaponProfile {
+  fire_rate: 0.6,
+  projectiles: 1,
+  spread: 0.,
+  burst: 1,
+  burst_interval: 0.,
+  projectile_speed: 120.,
+  damage: -40.,
+  scale: 1.,
+  muzzle_offset: Vec3::new(-3., 0., 0.),
+  projectile: ProjectileKind::Beam,
+};
+const DROPSHIP_NAME: &str = "dropship";
+
+//time before the first drop, then between each one
+const DROPSHIP_FIRST_RELEASE_TIME: f32 = 1.5;
+const DROPSHIP_RELEASE_TIME: f32 = 2.5;
+//what gets dropped, in order, once it's empty it just flies on
+const DROPSHIP_PAYLOAD: [EnemyKind; 6] = [
+  EnemyKind::Mine,
+  EnemyKind::Sidewinder,
+  EnemyKind::Mine,
+  EnemyKind::Sidewinder,
+  EnemyKind::Mine,
+  EnemyKind::Sidewinder,
+];
+//released enemies drop out of the hull, alternating above and below
+const DROPSHIP_RELEASE_OFFSET: Vec3 = Vec3::new(1.0, 0., 4.0);
+const DROPSHIP_RELEASE_SPEED: f32 = 8.0;
+
+const DROPSHIP_ROLL_SPEED: f32 = 0.4;
+const DROPSHIP_WRECK_TIME: f32 = 3.0;
+const DROPSHIP_BLAST_SIZE: f32 = 6.0;
+
+const DROPSHIP_POINTS: u64 = 500;
+//hull breaks up into a couple of loose mines
+const DROPSHIP_WRECK_DROPS: [EnemyKind; 2] = [EnemyKind::Mine, EnemyKind::Mine];
 
 pub struct DropshipPlugin;
 
-impl Plugin for DropshipPlugin{
-  fn build(&self, app: &mut App) {    
- //   app.add_systems(PreStartup, register_ai);  
+impl Plugin for DropshipPlugin {
+  fn build(&self, app: &mut App) {
+    app.add_systems(
+      FixedUpdate,
+      (spawn_dropship, release_payload).in_set(GameSchedule::EntityUpdates),
+    );
   }
 }
 
 #[derive(Component)]
-pub struct DropShip{
+#[require(Enemy, BoundsDespawn, HitMarker)]
+struct DropShip {
+  release_timer: Timer,
+  released: usize,
+}
+
+impl DropShip {
+  fn new() -> Self {
+    Self {
+      release_timer: Timer::from_seconds(DROPSHIP_FIRST_RELEASE_TIME, TimerMode::Once),
+      released: 0,
+    }
+  }
+}
+
+fn collider_shape() -> ColliderShape {
+  let engine = |side: f32| {
+    (
+      Transform::from_translation(DROPSHIP_ENGINE_OFFSET * Vec3::new(1., side, 1.)),
+      ColliderShape::Box(DROPSHIP_ENGINE_SIZE),
+    )
+  };
+  ColliderShape::Compound(vec![
+    (
+      Transform::from_translation(DROPSHIP_HULL_OFFSET),
+      ColliderShape::Box(DROPSHIP_HULL_SIZE),
+    ),
+    engine(1.),
+    engine(-1.),
+  ])
+}
+
+fn spawn_dropship(
+  mut commands: Commands,
+  mut ev_spawn_enemy_reader: EventReader<SpawnEnemyEvent>,
+  scene_assets: Res<SceneAssets>,
+  ai_configs: Res<AiConfigCollection>,
+) {
+  for &SpawnEnemyEvent {
+    kind,
+    translation,
+    velocity,
+  } in ev_spawn_enemy_reader.read()
+  {
+    if kind != EnemyKind::Dropship {
+      continue;
+    }
+    commands.spawn((
+      DropShip::new(),
+      SceneRoot(scene_assets.dropship.clone()),
+      Transform::from_translation(translation).with_rotation(Quat::from_rotation_z(PI)),
+      Velocity(velocity.unwrap_or(Vec3::new(DROPSHIP_SPEED, 0., 0.))),
+      Acceleration::new(Vec3::ZERO, 0., DROPSHIP_MAX_SPEED),
+      Collider::shaped(collider_shape(), DROPSHIP_COLLISION_DAMAGE),
+      Hookable::new(
+        DROPSHIP_HOOK_TRANSLATION,
+        Quat::from_rotation_z(DROPSHIP_HOOK_ROTATION),
+      ),
+      Heavy(DROPSHIP_CAPTOR_SPEED),
+      Health::new(DROPSHIP_HEALTH),
+      Weapon::new(None, Some(DROPSHIP_CAPTURED_WEAPON)),
+      Destroyable::new(
+        Remains::Wreck {
+          scene: scene_assets.dropship.clone(),
+          roll_speed: DROPSHIP_ROLL_SPEED,
+          time_to_live: DROPSHIP_WRECK_TIME,
+        },
+        DROPSHIP_BLAST_SIZE,
+        DROPSHIP_POINTS,
+      )
+      .with_drops(DROPSHIP_WRECK_DROPS.to_vec()),
+      ai_configs.get_behaviour(DROPSHIP_NAME, ""),
+    ));
+  }
+}
+
+//stops dropping once it's been caught, and stays empty if it's thrown
+type FreeFlying = (Without<Hooked>, Without<Jettisoned>);
 
+fn release_payload(
+  mut query: Query<(&mut DropShip, &GlobalTransform, &Velocity), FreeFlying>,
+  time: Res<Time>,
+  mut ev_spawn_enemy_writer: EventWriter<SpawnEnemyEvent>,
+) {
+  for (mut dropship, transform, velocity) in query.iter_mut() {
+    let Some(&kind) = DROPSHIP_PAYLOAD.get(dropship.released) else {
+      continue;
+    };
+    dropship.release_timer.tick(time.delta());
+    if !dropship.release_timer.finished() {
+      continue;
+    }
+    dropship
+      .release_timer
+      .set_duration(Duration::from_secs_f32(DROPSHIP_RELEASE_TIME));
+    dropship.release_timer.reset();
+
+    let side = if dropship.released % 2 == 0 { 1. } else { -1. };
+    let offset = DROPSHIP_RELEASE_OFFSET * Vec3::new(1., 1., side);
+    let release_velocity = match kind {
+      //mines tumble out and drift off with the ship
+      EnemyKind::Mine => Some(velocity.0 + Vec3::new(0., 0., side * DROPSHIP_RELEASE_SPEED)),
+      //everything else heads off under its own steam
+      _ => None,
+    };
+    ev_spawn_enemy_writer.write(SpawnEnemyEvent::new(
+      kind,
+      transform.translation() + offset,
+      release_velocity,
+    ));
+    dropship.released += 1;
+  }
 }
 
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    bullet::ShootEvent,
+    headless::HeadlessApp,
+    health::DamageSource,
+    input::{InputEventAction, InputEventType, InputTriggerEvent},
+    ship::{Captured, PlayerShip},
+  };
+
+  #[test]
+  fn captured_dropship_fires_a_beam_and_weighs_its_captor_down() {
+    let mut game = HeadlessApp::new();
+    game.record::<ShootEvent>();
+    let ship = game.find::<PlayerShip>().expect("ship spawned");
+    let at = game.world().get::<GlobalTransform>(ship).unwrap().translation();
+    let top_speed = game.world().get::<Acceleration>(ship).unwrap().max_speed;
+
+    //sat still in the path of the hook
+    game.send(SpawnEnemyEvent::new(
+      EnemyKind::Dropship,
+      at + Vec3::new(-20., 0., 0.),
+      Some(Vec3::ZERO),
+    ));
+    game.step();
+    let dropship = game.find::<DropShip>().expect("dropship spawned");
+    game.send(InputTriggerEvent::new(
+      InputEventAction::Shoot,
+      InputEventType::Pressed,
+    ));
+    game.step_secs(2.);
+    let captured = game.world().get::<Captured>(dropship).expect("dropship captured");
+    assert_eq!(captured.captor, ship);
+    let slowed = game.world().get::<Acceleration>(ship).unwrap().max_speed;
+    assert_eq!(slowed, top_speed * DROPSHIP_CAPTOR_SPEED);
+
+    game.step_secs(2.);
+    let beams: Vec<_> = game
+      .recorded::<ShootEvent>()
+      .iter()
+      .filter(|shot| matches!(shot.source, DamageSource::CaptiveShot(_)))
+      .collect();
+    assert!(!beams.is_empty());
+    assert!(beams.iter().all(|shot| shot.kind == ProjectileKind::Beam));
+    //out ahead, at the enemies
+    assert!(beams.iter().all(|shot| shot.velocity.x < 0.));
+  }
+}
@@ -27,7 +27,7 @@ const RICOCHET_ANIMATION_FPS: f32 = 15.0;
 const RICOCHET_ANIMATION_LENGTH: f32 = (1. / RICOCHET_ANIMATION_FPS) * (RICOCHET_FRAMES - 1) as f32;
 
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
 pub enum EffectSpriteType{
   Splosion,
   Ricochet,
@@ -56,7 +56,7 @@ impl EffectSpriteEvent {
 struct EffectQuad(Handle<Mesh>);
 
 #[derive(Component)]
-struct EffectSprite {
+pub struct EffectSprite {
   timer: Timer,
 }
 
@@ -75,7 +75,7 @@ impl Plugin for EffectSpritePlugin {
       .add_systems(Startup, init_effect_sprites)
       //.add_systems(Startup, _test.after(init_splosion))
       .add_systems(
-        Update,
+        FixedUpdate,
         (spawn_effect_sprites, update_effect_sprites).in_set(GameSchedule::EntityUpdates),
       );
   }
@@ -184,6 +184,7 @@ fn spawn_effect_sprites(
     effect,
   } in ev_effect_reader.read()
   {
+    //cosmetic, kept off the game rng so what's drawn can't change how a game plays out
     let mut rng = rand::thread_rng();
     let rotation = rng.gen_range(-1. ..1.);
     let mut transform = Transform::from_translation(translation)
@@ -1,4 +1,7 @@
 use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::collision_detection::CollisionLayer;
 
 pub const ENEMY_START_POINT_X: f32 = -70.0;
 //pub const ENEMY_START_POINT_Z_BOUNDS_MIN:f32 = -26.0;
@@ -7,10 +10,38 @@ pub const ENEMY_START_POINT_Z_BOUNDS_MAX: f32 = 26.0;
 pub struct EnemyPlugin;
 
 impl Plugin for EnemyPlugin {
-  fn build(&self, _app: &mut App) {
+  fn build(&self, app: &mut App) {
+    app.add_event::<SpawnEnemyEvent>();
     //   app.add_systems(Update, bounds_check.in_set(GameSchedule::DespawnEntities));
   }
 }
 
 #[derive(Component, Default)]
+#[require(CollisionLayer = CollisionLayer::Enemy)]
 pub struct Enemy;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EnemyKind {
+  Sidewinder,
+  Mine,
+  Dropship,
+}
+
+#[derive(Event)]
+pub struct SpawnEnemyEvent {
+  pub kind: EnemyKind,
+  pub translation: Vec3,
+  ///None lets the enemy pick its usual entry velocity
+  pub velocity: Option<Vec3>,
+}
+
+impl SpawnEnemyEvent {
+  pub fn new(kind: EnemyKind, translation: Vec3, velocity: Option<Vec3>) -> Self {
+    Self {
+      kind,
+      translation,
+      velocity,
+    }
+  }
+}
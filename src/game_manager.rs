@@ -2,80 +2,305 @@ use bevy::prelude::*;
 
 const GAME_START_LIVES: u32 = 2;
 const GAME_RESPAWN_TIME: f32 = 4.;
-use crate::{scheduling::GameSchedule, state::GameState};
+///most players that can share a game
+pub const MAX_PLAYERS: usize = 2;
+use crate::{
+  bullet::Bullet,
+  combo::Combo,
+  death::DeathEvent,
+  effect_sprite::EffectSprite,
+  enemy::Enemy,
+  health::DamageSource,
+  hook::Hook,
+  scheduling::GameSchedule,
+  ship::{PlayerNumber, PlayerShip},
+  state::{GameState, GameStateEvent},
+  wreck::Wreck,
+};
 
 pub struct GameManagerPlugin;
 
 impl Plugin for GameManagerPlugin {
   fn build(&self, app: &mut App) {
     app
+      .init_resource::<Players>()
       .add_systems(OnEnter(GameState::Playing), init_game)
-      .add_systems(OnEnter(PlayState::Dead), start_respawn_timer)
+      .add_systems(OnExit(GameState::GameOver), clear_game)
+      .add_systems(OnEnter(GameState::Menu), clear_game)
       .add_systems(
-        Update,
-        (respawn_player)
+        FixedUpdate,
+        (respawn_players)
           .in_set(GameSchedule::EntityUpdates)
-          .run_if(in_state(PlayState::Dead)),
+          .run_if(not(in_state(PlayState::NotInGame))),
       )
       .add_systems(
-        Update,
+        FixedUpdate,
         point_update.in_set(GameSchedule::PreDespawnEntities),
       )
+      .add_observer(score_kill)
+      .add_observer(ship_lost)
       .init_state::<PlayState>()
-      .add_event::<PointEvent>();
+      .add_event::<PointEvent>()
+      .add_event::<SpawnShipEvent>();
   }
 }
 
+#[derive(Event, Clone)]
+pub struct PointEvent {
+  pub points: u64,
+  ///who made the kill, bonuses depend on it
+  pub source: DamageSource,
+}
+
+impl PointEvent {
+  pub fn new(points: u64, source: DamageSource) -> Self {
+    Self { points, source }
+  }
+}
+
+///a player's ship is due to come in
 #[derive(Event)]
-pub struct PointEvent(pub u64);
+pub struct SpawnShipEvent {
+  pub player: usize,
+}
+
+impl SpawnShipEvent {
+  pub fn new(player: usize) -> Self {
+    Self { player }
+  }
+}
+
+///how many are playing, picked on the title screen
+#[derive(Resource)]
+pub struct Players(pub usize);
+
+impl Default for Players {
+  fn default() -> Self {
+    Self(1)
+  }
+}
 
 #[derive(States, Debug, Clone, PartialEq, Eq, Hash, Default, Copy)]
 pub enum PlayState {
   #[default]
   NotInGame,
+  ///every ship is down, waiting on respawns
   Dead,
+  ///at least one ship is flying
   Alive,
 }
 
 #[derive(Component)]
+#[require(Combo)]
 pub struct Game {
+  ///everyone's points together
+  pub score: u64,
+  pub wave: u32,
+  pub players: Vec<PlayerRecord>,
+}
+
+impl Game {
+  ///what goes on the high score table, the best of the players when they're competing
+  pub fn final_score(&self, shared_score: bool) -> u64 {
+    if shared_score || self.players.len() < 2 {
+      return self.score;
+    }
+    self
+      .players
+      .iter()
+      .map(|record| record.score)
+      .max()
+      .unwrap_or_default()
+  }
+}
+
+///how one player's getting on
+pub struct PlayerRecord {
   pub score: u64,
   pub lives: u32,
-  respawn_timer: Timer,
+  ///running while their ship is waiting to come back in
+  respawn_timer: Option<Timer>,
+  ///lost their last ship
+  pub out: bool,
 }
 
-fn point_update(mut game: Single<&mut Game>, mut ev_point_reader: EventReader<PointEvent>) {
-  for point in ev_point_reader.read() {
-    game.score += point.0;
-    info!("score: {:?}", game.score);
+impl Default for PlayerRecord {
+  fn default() -> Self {
+    Self {
+      score: 0,
+      lives: GAME_START_LIVES,
+      respawn_timer: None,
+      out: false,
+    }
   }
 }
 
-fn start_respawn_timer(mut game: Single<&mut Game>) {
-  game.respawn_timer.reset();
+fn score_kill(trigger: Trigger<DeathEvent>, mut ev_point_writer: EventWriter<PointEvent>) {
+  if trigger.points > 0 {
+    ev_point_writer.write(PointEvent::new(trigger.points, trigger.source));
+  }
 }
 
-fn respawn_player(
+fn point_update(
+  game_query: Single<(&mut Game, &mut Combo)>,
+  mut ev_point_reader: EventReader<PointEvent>,
+) {
+  let (mut game, mut combo) = game_query.into_inner();
+  for &PointEvent { points, source } in ev_point_reader.read() {
+    let awarded = combo.award(points, source);
+    game.score += awarded;
+    if let Some(record) = source
+      .player()
+      .and_then(|player| game.players.get_mut(player))
+    {
+      record.score += awarded;
+    }
+    info!("score: {:?} (x{})", game.score, combo.multiplier());
+  }
+}
+
+//a ship's gone, start its player's wait for the next one
+fn ship_lost(
+  trigger: Trigger<DeathEvent>,
+  ship_query: Query<(Entity, &PlayerNumber), With<PlayerShip>>,
+  mut game_query: Query<&mut Game>,
+  mut play_state: ResMut<NextState<PlayState>>,
+) {
+  let Ok((_, number)) = ship_query.get(trigger.target()) else {
+    return;
+  };
+  let Ok(mut game) = game_query.single_mut() else {
+    return;
+  };
+  if let Some(record) = game.players.get_mut(number.0) {
+    record.respawn_timer = Some(Timer::from_seconds(GAME_RESPAWN_TIME, TimerMode::Once));
+  }
+  if ship_query.iter().all(|(ship, _)| ship == trigger.target()) {
+    play_state.set(PlayState::Dead);
+  }
+}
+
+fn respawn_players(
   mut game: Single<&mut Game>,
   time: Res<Time>,
   mut play_state: ResMut<NextState<PlayState>>,
+  mut ev_spawn_ship_writer: EventWriter<SpawnShipEvent>,
+  mut ev_game_state_writer: EventWriter<GameStateEvent>,
 ) {
-  game.respawn_timer.tick(time.delta());
-  if game.respawn_timer.just_finished() {
-    if game.lives > 0 {
-      game.lives -= 1;
-      //TODO: goto end screen
+  let mut went_out = false;
+  for (player, record) in game.players.iter_mut().enumerate() {
+    let Some(timer) = record.respawn_timer.as_mut() else {
+      continue;
+    };
+    timer.tick(time.delta());
+    if !timer.just_finished() {
+      continue;
     }
-    info!("spawning player, ships left:{:?}", game.lives);
+    record.respawn_timer = None;
+    if record.lives == 0 {
+      info!("player {} is out, score:{:?}", player + 1, record.score);
+      record.out = true;
+      went_out = true;
+      continue;
+    }
+    record.lives -= 1;
+    info!(
+      "spawning player {}, ships left:{:?}",
+      player + 1,
+      record.lives
+    );
+    ev_spawn_ship_writer.write(SpawnShipEvent::new(player));
     play_state.set(PlayState::Alive);
   }
+  if went_out && game.players.iter().all(|record| record.out) {
+    info!("game over, final score:{:?}", game.score);
+    ev_game_state_writer.write(GameStateEvent::new(GameState::GameOver));
+  }
 }
 
-fn init_game(mut commands: Commands, mut next_state: ResMut<NextState<PlayState>>) {
+type GameEntities = Or<(
+  With<Game>,
+  With<PlayerShip>,
+  With<Hook>,
+  With<Enemy>,
+  With<Bullet>,
+  With<Wreck>,
+  With<EffectSprite>,
+)>;
+
+//clear out everything left over from the last game
+fn clear_game(
+  mut commands: Commands,
+  mut play_state: ResMut<NextState<PlayState>>,
+  query: Query<Entity, GameEntities>,
+) {
+  for entity in query.iter() {
+    commands.entity(entity).try_despawn();
+  }
+  play_state.set(PlayState::NotInGame);
+}
+
+fn init_game(
+  mut commands: Commands,
+  players: Res<Players>,
+  mut next_state: ResMut<NextState<PlayState>>,
+  mut ev_spawn_ship_writer: EventWriter<SpawnShipEvent>,
+) {
   commands.spawn(Game {
     score: 0,
-    lives: GAME_START_LIVES,
-    respawn_timer: Timer::from_seconds(GAME_RESPAWN_TIME, TimerMode::Once),
+    wave: 0,
+    players: (0..players.0).map(|_| PlayerRecord::default()).collect(),
   });
+  for player in 0..players.0 {
+    ev_spawn_ship_writer.write(SpawnShipEvent::new(player));
+  }
   next_state.set(PlayState::Alive);
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    headless::HeadlessApp,
+    health::{DamageSource, HealthEvent},
+  };
+
+  fn ships(game: &mut HeadlessApp) -> Vec<(Entity, usize)> {
+    let world = game.app.world_mut();
+    world
+      .query_filtered::<(Entity, &PlayerNumber), With<PlayerShip>>()
+      .iter(world)
+      .map(|(entity, number)| (entity, number.0))
+      .collect()
+  }
+
+  #[test]
+  fn lost_ship_comes_back_without_the_other_player() {
+    let mut game = HeadlessApp::with_players(2);
+    let mut spawned = ships(&mut game);
+    spawned.sort_by_key(|(_, player)| *player);
+    assert_eq!(spawned.len(), 2);
+    assert_eq!(spawned[1].1, 1);
+
+    game.send(HealthEvent::new(spawned[1].0, -1000., DamageSource::Enemy));
+    game.step_frames(2);
+    assert_eq!(
+      ships(&mut game)
+        .iter()
+        .map(|(_, player)| *player)
+        .collect::<Vec<_>>(),
+      [0]
+    );
+    //the other ship's still flying
+    assert_eq!(
+      *game.world().resource::<State<PlayState>>().get(),
+      PlayState::Alive
+    );
+
+    game.step_secs(GAME_RESPAWN_TIME as f64 + 0.1);
+    assert_eq!(ships(&mut game).len(), 2);
+    let players = &game.single::<Game>().players;
+    assert_eq!(players[0].lives, GAME_START_LIVES);
+    assert_eq!(players[1].lives, GAME_START_LIVES - 1);
+  }
+}
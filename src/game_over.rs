@@ -0,0 +1,250 @@
+use bevy::prelude::*;
+
+use crate::{
+  asset_loader::SceneAssets,
+  game_manager::Game,
+  high_scores::{spawn_high_score_table, HighScoreEntry, HighScores, HIGH_SCORE_NAME_LENGTH},
+  menu::{
+    menu_heading, menu_item, menu_root, menu_title, MenuAction, MenuActionEvent, MenuAdjustEvent,
+    MenuItem,
+  },
+  replay::watching_replay,
+  settings::Settings,
+  state::{GameState, GameStateEvent},
+};
+
+const GAME_OVER_SCORE_FONT_SIZE: f32 = 30.;
+const GAME_OVER_HINT_FONT_SIZE: f32 = 20.;
+const NAME_LETTERS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+pub struct GameOverPlugin;
+
+impl Plugin for GameOverPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .add_sub_state::<GameOverPage>()
+      .init_resource::<NameEntry>()
+      //a replay that's run its course goes straight back to the title
+      .add_systems(
+        OnEnter(GameOverPage::EnterName),
+        start_name_entry.run_if(not(watching_replay)),
+      )
+      .add_systems(
+        OnEnter(GameOverPage::Summary),
+        spawn_game_over_screen.run_if(not(watching_replay)),
+      )
+      .add_systems(
+        Update,
+        (name_entry_actions, update_name_label)
+          .chain()
+          .run_if(in_state(GameOverPage::EnterName)),
+      )
+      .add_systems(
+        Update,
+        game_over_actions.run_if(in_state(GameOverPage::Summary)),
+      );
+  }
+}
+
+#[derive(SubStates, Debug, Clone, PartialEq, Eq, Hash, Default, Copy)]
+#[source(GameState = GameState::GameOver)]
+#[states(scoped_entities)]
+pub enum GameOverPage {
+  ///skipped straight past when the score doesn't make the table
+  #[default]
+  EnterName,
+  Summary,
+}
+
+//initials being picked, and where they ended up on the table
+#[derive(Resource, Default)]
+struct NameEntry {
+  letters: [usize; HIGH_SCORE_NAME_LENGTH],
+  cursor: usize,
+  place: Option<usize>,
+}
+
+impl NameEntry {
+  fn name(&self) -> String {
+    self
+      .letters
+      .iter()
+      .map(|&letter| NAME_LETTERS[letter] as char)
+      .collect()
+  }
+
+  //current letter in brackets
+  fn label(&self) -> String {
+    self
+      .letters
+      .iter()
+      .enumerate()
+      .map(|(index, &letter)| {
+        let letter = NAME_LETTERS[letter] as char;
+        if index == self.cursor {
+          format!("[{}]", letter)
+        } else {
+          format!(" {} ", letter)
+        }
+      })
+      .collect()
+  }
+}
+
+fn game_over_text(text: String, font: Handle<Font>, font_size: f32) -> impl Bundle {
+  (
+    Text::new(text),
+    TextFont {
+      font,
+      font_size,
+      ..default()
+    },
+  )
+}
+
+fn start_name_entry(
+  mut commands: Commands,
+  game: Single<&Game>,
+  high_scores: Res<HighScores>,
+  settings: Res<Settings>,
+  scene_assets: Res<SceneAssets>,
+  mut name_entry: ResMut<NameEntry>,
+  mut next_page: ResMut<NextState<GameOverPage>>,
+) {
+  *name_entry = NameEntry::default();
+  let score = game.final_score(settings.shared_score);
+  if !high_scores.qualifies(score) {
+    next_page.set(GameOverPage::Summary);
+    return;
+  }
+  let font = scene_assets.font.clone();
+  commands
+    .spawn((StateScoped(GameOverPage::EnterName), menu_root()))
+    .with_children(|parent| {
+      parent.spawn(menu_title("Game Over", font.clone()));
+      parent.spawn(menu_heading(
+        &format!("New high score: {}", score),
+        font.clone(),
+      ));
+      parent.spawn(game_over_text(
+        "Left / right to pick a letter, fire to confirm".into(),
+        font.clone(),
+        GAME_OVER_HINT_FONT_SIZE,
+      ));
+      parent.spawn(menu_item(
+        0,
+        &name_entry.label(),
+        MenuAction::EnterName,
+        font.clone(),
+      ));
+    });
+}
+
+fn name_entry_actions(
+  mut ev_menu_action_reader: EventReader<MenuActionEvent>,
+  mut ev_menu_adjust_reader: EventReader<MenuAdjustEvent>,
+  mut name_entry: ResMut<NameEntry>,
+  mut high_scores: ResMut<HighScores>,
+  game: Single<&Game>,
+  settings: Res<Settings>,
+  mut next_page: ResMut<NextState<GameOverPage>>,
+) {
+  for MenuAdjustEvent { action, step } in ev_menu_adjust_reader.read() {
+    if *action != MenuAction::EnterName {
+      continue;
+    }
+    let cursor = name_entry.cursor;
+    let letter = name_entry.letters[cursor] as i32 + step;
+    name_entry.letters[cursor] = letter.rem_euclid(NAME_LETTERS.len() as i32) as usize;
+  }
+  for MenuActionEvent(action) in ev_menu_action_reader.read() {
+    if *action != MenuAction::EnterName {
+      continue;
+    }
+    if name_entry.cursor + 1 < HIGH_SCORE_NAME_LENGTH {
+      name_entry.cursor += 1;
+      continue;
+    }
+    let entry = HighScoreEntry::new(
+      name_entry.name(),
+      game.final_score(settings.shared_score),
+      game.wave,
+    );
+    name_entry.place = high_scores.insert(entry);
+    high_scores.save();
+    next_page.set(GameOverPage::Summary);
+    return;
+  }
+}
+
+fn update_name_label(mut query: Query<(&MenuItem, &mut Text)>, name_entry: Res<NameEntry>) {
+  if !name_entry.is_changed() {
+    return;
+  }
+  for (item, mut text) in query.iter_mut() {
+    if item.action == MenuAction::EnterName {
+      text.0 = name_entry.label();
+    }
+  }
+}
+
+fn spawn_game_over_screen(
+  mut commands: Commands,
+  game: Single<&Game>,
+  high_scores: Res<HighScores>,
+  name_entry: Res<NameEntry>,
+  settings: Res<Settings>,
+  scene_assets: Res<SceneAssets>,
+) {
+  let font = scene_assets.font.clone();
+  commands
+    .spawn((StateScoped(GameOverPage::Summary), menu_root()))
+    .with_children(|parent| {
+      parent.spawn(menu_title("Game Over", font.clone()));
+      parent.spawn((
+        game_over_text(
+          format!(
+            "Final score: {}  wave {}",
+            game.final_score(settings.shared_score),
+            game.wave
+          ),
+          font.clone(),
+          GAME_OVER_SCORE_FONT_SIZE,
+        ),
+        Node {
+          margin: UiRect::bottom(Val::Px(20.)),
+          ..default()
+        },
+      ));
+      spawn_high_score_table(parent, &high_scores, font.clone(), name_entry.place);
+      parent.spawn(menu_item(
+        0,
+        "Play again",
+        MenuAction::PlayAgain,
+        font.clone(),
+      ));
+      parent.spawn(menu_item(
+        1,
+        "Main menu",
+        MenuAction::MainMenu,
+        font.clone(),
+      ));
+    });
+}
+
+fn game_over_actions(
+  mut ev_menu_action_reader: EventReader<MenuActionEvent>,
+  mut ev_game_state_writer: EventWriter<GameStateEvent>,
+) {
+  for MenuActionEvent(action) in ev_menu_action_reader.read() {
+    match action {
+      MenuAction::PlayAgain => {
+        ev_game_state_writer.write(GameStateEvent::new(GameState::Playing));
+      }
+      MenuAction::MainMenu => {
+        ev_game_state_writer.write(GameStateEvent::new(GameState::Menu));
+      }
+      _ => {}
+    }
+  }
+}
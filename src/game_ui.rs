@@ -2,10 +2,17 @@ use bevy::prelude::*;
 
 use crate::{
   asset_loader::SceneAssets,
-  game_manager::{Game, PlayState},
+  combo::Combo,
+  game_manager::{Game, PlayerRecord},
   scheduling::GameSchedule,
+  settings::Settings,
+  state::GameState,
 };
 
+const COMBO_BAR_WIDTH: f32 = 80.;
+const COMBO_BAR_HEIGHT: f32 = 4.;
+const COMBO_BAR_COLOUR: Color = Color::srgb(2.0, 1.8, 0.2);
+
 pub struct GameUiPlugin;
 
 #[derive(Component)]
@@ -14,21 +21,66 @@ struct LivesDisplay;
 #[derive(Component)]
 struct ScoreDisplay;
 
+#[derive(Component)]
+struct ComboDisplay;
+
+#[derive(Component)]
+struct ComboTimerBar;
+
 impl Plugin for GameUiPlugin {
   fn build(&self, app: &mut App) {
     app
-      .add_systems(Startup, init_game_ui)
-      .add_systems(Update,score_update.in_set(GameSchedule::DespawnEntities))
-      .add_systems(OnEnter(PlayState::Alive), lives_update);
+      .add_systems(OnEnter(GameState::Playing), init_game_ui)
+      .add_systems(FixedUpdate,(score_update, combo_update, lives_update).in_set(GameSchedule::DespawnEntities));
   }
 }
 
-fn score_update(mut score_display: Single<&mut Text, With<ScoreDisplay>>, game: Single<&Game>) {
-  score_display.0 = format!("Score: {}", game.score);
+//one score for the team, or one each when co-op players are competing
+fn score_update(
+  mut score_display: Single<&mut Text, With<ScoreDisplay>>,
+  game: Single<&Game>,
+  settings: Res<Settings>,
+) {
+  score_display.0 = if settings.shared_score || game.players.len() < 2 {
+    format!("Score: {}", game.score)
+  } else {
+    per_player(&game, |record| record.score.to_string())
+  };
+}
+
+fn combo_update(
+  mut combo_display: Single<&mut Text, With<ComboDisplay>>,
+  mut combo_bar: Single<&mut Node, With<ComboTimerBar>>,
+  combo: Single<&Combo>,
+) {
+  let remaining = combo.time_remaining();
+  combo_display.0 = if remaining > 0. {
+    format!("x{}", combo.multiplier())
+  } else {
+    String::new()
+  };
+  combo_bar.width = Val::Px(COMBO_BAR_WIDTH * remaining);
 }
 
 fn lives_update(mut life_display: Single<&mut Text, With<LivesDisplay>>, game: Single<&Game>) {
-  life_display.0 = format!("Ships: {}", game.lives);
+  life_display.0 = match game.players.as_slice() {
+    [record] => format!("Ships: {}", record.lives),
+    _ => format!(
+      "Ships  {}",
+      per_player(&game, |record| record.lives.to_string())
+    ),
+  };
+}
+
+//"P1 ...  P2 ..." across the players
+fn per_player(game: &Game, value: impl Fn(&PlayerRecord) -> String) -> String {
+  game
+    .players
+    .iter()
+    .enumerate()
+    .map(|(player, record)| format!("P{} {}", player + 1, value(record)))
+    .collect::<Vec<_>>()
+    .join("  ")
 }
 
 fn init_game_ui(
@@ -40,6 +92,7 @@ fn init_game_ui(
   //let image:Handle<Image> = asset_server.load("ui/life.png");
 
   commands.spawn((
+    StateScoped(GameState::Playing),
     LivesDisplay,
     Text::new("Lives"),
     TextFont {
@@ -57,6 +110,7 @@ fn init_game_ui(
 
   commands
     .spawn((
+      StateScoped(GameState::Playing),
       Node {
         width: Val::Percent(100.),
         flex_direction: FlexDirection::Column,
@@ -81,6 +135,26 @@ fn init_game_ui(
         },
         //Outline::new(Val::Px(1.), Val::ZERO, BLUE.into()),
       ));
+      parent.spawn((
+        ComboDisplay,
+        Text::new(""),
+        TextFont {
+          font: scene_assets.font.clone(),
+          font_size: 20.,
+          ..default()
+        },
+        TextColor(COMBO_BAR_COLOUR),
+      ));
+      //shrinks away as the combo window runs out
+      parent.spawn((
+        ComboTimerBar,
+        Node {
+          width: Val::Px(0.),
+          height: Val::Px(COMBO_BAR_HEIGHT),
+          ..default()
+        },
+        BackgroundColor(COMBO_BAR_COLOUR),
+      ));
     });
 
 }
@@ -0,0 +1,194 @@
+//! the game without a window, for tests. built for the crate's own tests, and for
+//! integration tests with the `headless` feature
+use std::{
+  thread,
+  time::{Duration, Instant},
+};
+
+use bevy::{input::InputPlugin, prelude::*, state::app::StatesPlugin, time::TimeUpdateStrategy};
+
+use crate::{
+  asset_loader::{AssetsLoading, SceneAssets},
+  audio::SfxEvent,
+  collision_detection::{Collider, CollisionLayer},
+  effect_sprite::EffectSpriteEvent,
+  game_manager::{PlayState, Players},
+  health::Health,
+  level::LevelPlugin,
+  rng::GameRng,
+  scheduling::GAME_STEP_HZ,
+  state::GameState,
+  GameplayPlugins,
+};
+
+//every frame moves the clock on by exactly one fixed step
+const HEADLESS_FRAME_SECONDS: f64 = 1. / GAME_STEP_HZ;
+const HEADLESS_SEED: u64 = 2;
+const HEADLESS_LOAD_TIMEOUT: Duration = Duration::from_secs(10);
+//enough frames to get through the state changes into a live ship
+const HEADLESS_START_FRAMES: usize = 3;
+//targets sit in a row well away from the ship, shots are fired along it
+
+///the gameplay plugins without a window, renderer or audio, driven one frame at a time
+pub struct HeadlessApp {
+  pub app: App,
+}
+
+///events of one type seen so far, see `HeadlessApp::record`
+#[derive(Resource)]
+struct Recorded<E: Event>(Vec<E>);
+
+impl HeadlessApp {
+  ///a game in progress with the player's ship spawned and no waves,
+  ///tests spawn whatever they need themselves
+  pub fn new() -> Self {
+    Self::with_seed(HEADLESS_SEED)
+  }
+
+  pub fn with_seed(seed: u64) -> Self {
+    Self::start(seed, 1)
+  }
+
+  ///a co-op game with a ship for each player
+  pub fn with_players(players: usize) -> Self {
+    Self::start(HEADLESS_SEED, players)
+  }
+
+  fn start(seed: u64, players: usize) -> Self {
+    let mut app = App::new();
+    app
+      .add_plugins((
+        MinimalPlugins,
+        AssetPlugin::default(),
+        StatesPlugin,
+        TransformPlugin,
+        InputPlugin,
+      ))
+      .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+        HEADLESS_FRAME_SECONDS,
+      )))
+      //stand-ins for what the presentation plugins would provide
+      .init_asset::<Mesh>()
+      .init_asset::<StandardMaterial>()
+      .init_asset::<Image>()
+      .init_resource::<AssetsLoading>()
+      .init_resource::<SceneAssets>()
+      .add_event::<EffectSpriteEvent>()
+      .add_event::<SfxEvent>()
+      .add_plugins(GameplayPlugins.build().disable::<LevelPlugin>());
+
+    //run startup and let the data files load before skipping the menus
+    app.update();
+    wait_for_assets(&mut app);
+    app.world_mut().resource_mut::<GameRng>().next_seed = Some(seed);
+    app.insert_resource(Players(players));
+    app
+      .world_mut()
+      .resource_mut::<NextState<GameState>>()
+      .set(GameState::Playing);
+    let mut headless = Self { app };
+    headless.step_frames(HEADLESS_START_FRAMES);
+    assert_eq!(
+      *headless.app.world().resource::<State<PlayState>>().get(),
+      PlayState::Alive
+    );
+    headless
+  }
+
+  pub fn step(&mut self) {
+    self.app.update();
+  }
+
+  pub fn step_frames(&mut self, frames: usize) {
+    for _ in 0..frames {
+      self.step();
+    }
+  }
+
+  ///run at least `seconds` of game time
+  pub fn step_secs(&mut self, seconds: f64) {
+    let frames = (seconds / HEADLESS_FRAME_SECONDS).ceil() as usize;
+    self.step_frames(frames);
+  }
+
+  ///queue up an event to be read during the next step
+  pub fn send<E: Event>(&mut self, event: E) {
+    self.app.world_mut().send_event(event);
+  }
+
+  ///start keeping every `E` sent from now on, read them back with `recorded`
+  pub fn record<E: Event + Clone>(&mut self) {
+    self.app.insert_resource(Recorded::<E>(Vec::new()));
+    self.app.add_systems(Last, record_events::<E>);
+  }
+
+  pub fn recorded<E: Event>(&self) -> &[E] {
+    &self.app.world().resource::<Recorded<E>>().0
+  }
+
+  ///the first entity with a `C`
+  pub fn find<C: Component>(&mut self) -> Option<Entity> {
+    let world = self.app.world_mut();
+    world.query_filtered::<Entity, With<C>>().iter(world).next()
+  }
+
+  ///the one and only `C`, panicking if there isn't exactly one
+  pub fn single<C: Component>(&mut self) -> &C {
+    let world = self.app.world_mut();
+    world
+      .query::<&C>()
+      .single(world)
+      .expect("exactly one matching component")
+  }
+
+  pub fn world(&self) -> &World {
+    self.app.world()
+  }
+
+  ///a harmless enemy that sits still to be shot at
+  pub fn spawn_target(&mut self, translation: Vec3, radius: f32, health: f32) -> Entity {
+    let target = (
+      Transform::from_translation(translation),
+      Collider::new(radius, 0.),
+      CollisionLayer::Enemy,
+      Health::new(health),
+    );
+    self.app.world_mut().spawn(target).id()
+  }
+}
+
+impl Default for HeadlessApp {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+//ai configs have to be in before anything spawns, or they'd start with no behaviour
+fn wait_for_assets(app: &mut App) {
+  let start = Instant::now();
+  loop {
+    let world = app.world();
+    let asset_server = world.resource::<AssetServer>();
+    if world
+      .resource::<AssetsLoading>()
+      .0
+      .iter()
+      .all(|asset| asset_server.is_loaded_with_dependencies(asset.id()))
+    {
+      return;
+    }
+    assert!(
+      start.elapsed() < HEADLESS_LOAD_TIMEOUT,
+      "timed out loading assets"
+    );
+    thread::sleep(Duration::from_millis(1));
+    app.update();
+  }
+}
+
+fn record_events<E: Event + Clone>(
+  mut ev_reader: EventReader<E>,
+  mut recorded: ResMut<Recorded<E>>,
+) {
+  recorded.0.extend(ev_reader.read().cloned());
+}
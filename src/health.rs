@@ -8,24 +8,56 @@ impl Plugin for HealthPlugin {
   fn build(&self, app: &mut App) {
     app
       .add_systems(
-        Update,
+        FixedUpdate,
         apply_health_changes.in_set(GameSchedule::HealthAdjust),
       )
       .add_event::<HealthEvent>();
   }
 }
 
+///what dealt some damage, so kills can be credited, and to which player
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum DamageSource {
+  #[default]
+  Enemy,
+  ///a player's ship flying into something
+  ShipRam(usize),
+  ///a captured enemy's guns
+  CaptiveShot(usize),
+  ///a captured enemy swung or thrown into something
+  CaptiveRam(usize),
+  ///not an attack, like a ship healing itself. credited to nobody
+  Neutral,
+}
+
+impl DamageSource {
+  pub fn is_player(&self) -> bool {
+    self.player().is_some()
+  }
+
+  pub fn player(&self) -> Option<usize> {
+    match self {
+      DamageSource::Enemy | DamageSource::Neutral => None,
+      DamageSource::ShipRam(player)
+      | DamageSource::CaptiveShot(player)
+      | DamageSource::CaptiveRam(player) => Some(*player),
+    }
+  }
+}
+
 #[derive(Event)]
 pub struct HealthEvent {
   pub entity: Entity,
   pub health_adjustment: f32,
+  pub source: DamageSource,
 }
 
 impl HealthEvent {
-  pub fn new(entity: Entity, health_adjustment: f32) -> Self {
+  pub fn new(entity: Entity, health_adjustment: f32, source: DamageSource) -> Self {
     Self {
       entity,
       health_adjustment,
+      source,
     }
   }
 }
@@ -34,11 +66,17 @@ impl HealthEvent {
 pub struct Health {
   pub value: f32,
   pub max: f32,
+  ///source of the most recent damage, who gets the credit for a kill
+  pub last_hit: DamageSource,
 }
 
 impl Health {
   pub fn new(value: f32) -> Self {
-    Self { value, max: value }
+    Self {
+      value,
+      max: value,
+      last_hit: DamageSource::default(),
+    }
   }
 }
 
@@ -49,11 +87,15 @@ fn apply_health_changes(
   for HealthEvent {
     entity,
     health_adjustment,
+    source,
   } in ev_health_reader.read()
   {
     let Ok(mut health) = query.get_mut(*entity) else {
       continue;
     };
     health.value = (health.value + health_adjustment).min(health.max);
+    if *health_adjustment < 0. {
+      health.last_hit = *source;
+    }
   }
 }
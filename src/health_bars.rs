@@ -1,6 +1,6 @@
 use bevy::{color::palettes::css::*, prelude::*};
 
-use crate::{asset_loader::SceneAssets, health::Health, scheduling::GameSchedule, ship::{Captured, PlayerShip}};
+use crate::{asset_loader::SceneAssets, game_manager::Players, health::Health, scheduling::GameSchedule, ship::{Captured, PlayerNumber, PlayerShip}, state::GameState};
 
 
 const HEALTH_BAR_WIDTH_PER_HEALTH: f32 = 15. / 100.;
@@ -10,8 +10,8 @@ pub struct HealthBarsPlugin;
 
 impl Plugin for HealthBarsPlugin{
   fn build(&self, app: &mut App) {
-    app.add_systems(Startup, init_healthbars)
-    .add_systems(Update, (captive_health_update, health_update).in_set(GameSchedule::EntityUpdates));
+    app.add_systems(OnEnter(GameState::Playing), init_healthbars)
+    .add_systems(FixedUpdate, (captive_health_update, health_update).in_set(GameSchedule::EntityUpdates));
   }
 }
 
@@ -22,6 +22,10 @@ impl Plugin for HealthBarsPlugin{
 #[derive(Component)]
 struct CaptiveUi;
 
+///which player's bars these are
+#[derive(Component, Clone, Copy)]
+struct PlayerHud(usize);
+
 
 #[derive(Component, Default)]
 struct HealthBorder(f32);
@@ -39,68 +43,72 @@ struct CaptiveHealthBar(f32);
 
 fn health_update(
   //mut health_display: Single<&mut Text, With<HealthDisplay>>,
-  player_health_query: Query<&Health, With<PlayerShip>>,
-  healthbar_all: Single<(&mut HealthBar, &mut Node, &mut Visibility), Without<HealthBorder>>,
-  healthbar_container_all: Single<(&mut HealthBorder, &mut Node), Without<HealthBar>>,
+  player_health_query: Query<(&Health, &PlayerNumber), With<PlayerShip>>,
+  mut healthbar_query: Query<(&PlayerHud, &mut HealthBar, &mut Node, &mut Visibility), Without<HealthBorder>>,
+  mut healthbar_container_query: Query<(&PlayerHud, &mut HealthBorder, &mut Node), Without<HealthBar>>,
 ) {
-  let Ok(health) = player_health_query.single() else {
-    return;
-  };
-  let (mut healthbar, mut hb_node, mut visibility) = healthbar_all.into_inner();
-  let (mut healthbar_container, mut hbc_node) = healthbar_container_all.into_inner();
-
-  let mut force_health_update = false;
-  if healthbar_container.0 != health.max {
-    hbc_node.width = Val::Vw(HEALTH_BAR_WIDTH_PER_HEALTH * health.max);
-    healthbar_container.0 = health.max;
-    force_health_update = true;
-  }
-  if force_health_update || healthbar.0 != health.value {
-    if health.value <= 0.{
-      *visibility = Visibility::Hidden;
+  for (health, number) in player_health_query.iter() {
+    let Some((_, mut healthbar, mut hb_node, mut visibility)) = healthbar_query.iter_mut().find(|(hud, ..)| hud.0 == number.0) else {
+      continue;
+    };
+    let Some((_, mut healthbar_container, mut hbc_node)) = healthbar_container_query.iter_mut().find(|(hud, ..)| hud.0 == number.0) else {
+      continue;
+    };
+
+    let mut force_health_update = false;
+    if healthbar_container.0 != health.max {
+      hbc_node.width = Val::Vw(HEALTH_BAR_WIDTH_PER_HEALTH * health.max);
+      healthbar_container.0 = health.max;
+      force_health_update = true;
     }
-    else{
-      *visibility = Visibility::Visible;
+    if force_health_update || healthbar.0 != health.value {
+      if health.value <= 0.{
+        *visibility = Visibility::Hidden;
+      }
+      else{
+        *visibility = Visibility::Visible;
+      }
+      hb_node.width = Val::Percent((health.value / health.max) * 100.);
+      healthbar.0 = health.value;
     }
-    hb_node.width = Val::Percent((health.value / health.max) * 100.);
-    healthbar.0 = health.value;
   }
 }
 
 fn captive_health_update(
   //mut health_display: Single<&mut Text, With<HealthDisplay>>,
-  captive_health_query: Query<&Health, With<Captured>>,
-  captive_healthbar_all: Single<(&mut CaptiveHealthBar, &mut Node), Without<CaptiveHealthBorder>>,
-  captive_healthbar_container_all: Single<(&mut CaptiveHealthBorder, &mut Node), Without<CaptiveHealthBar>>,
-  mut captive_ui_query: Query<&mut Visibility, With<CaptiveUi>>,
+  captive_health_query: Query<(&Health, &PlayerNumber), With<Captured>>,
+  mut captive_healthbar_query: Query<(&PlayerHud, &mut CaptiveHealthBar, &mut Node), Without<CaptiveHealthBorder>>,
+  mut captive_healthbar_container_query: Query<(&PlayerHud, &mut CaptiveHealthBorder, &mut Node), Without<CaptiveHealthBar>>,
+  mut captive_ui_query: Query<(&PlayerHud, &mut Visibility), With<CaptiveUi>>,
 
 ) {
-
-  let Ok(health) = captive_health_query.single() else {
-    for mut visibility in &mut captive_ui_query {
-      *visibility = Visibility::Hidden;
-    }
-    return;
-  };
-
-  for mut visibility in &mut captive_ui_query {
-    *visibility = Visibility::Visible;
+  for (hud, mut visibility) in &mut captive_ui_query {
+    *visibility = if captive_health_query.iter().any(|(_, number)| number.0 == hud.0) {
+      Visibility::Visible
+    } else {
+      Visibility::Hidden
+    };
   }
 
-  let (mut healthbar, mut hb_node) = captive_healthbar_all.into_inner();
-  let (mut healthbar_container, mut hbc_node) = captive_healthbar_container_all.into_inner();
+  for (health, number) in captive_health_query.iter() {
+    let Some((_, mut healthbar, mut hb_node)) = captive_healthbar_query.iter_mut().find(|(hud, ..)| hud.0 == number.0) else {
+      continue;
+    };
+    let Some((_, mut healthbar_container, mut hbc_node)) = captive_healthbar_container_query.iter_mut().find(|(hud, ..)| hud.0 == number.0) else {
+      continue;
+    };
 
+    let mut force_health_update = false;
 
-  let mut force_health_update = false;
-
-  if healthbar_container.0 != health.max {
-    hbc_node.width = Val::Vw(HEALTH_BAR_WIDTH_PER_HEALTH * health.max);
-    healthbar_container.0 = health.max;
-    force_health_update = true;
-  }
-  if force_health_update || healthbar.0 != health.value {
-    hb_node.width = Val::Percent((health.value / health.max) * 100.);
-    healthbar.0 = health.value;
+    if healthbar_container.0 != health.max {
+      hbc_node.width = Val::Vw(HEALTH_BAR_WIDTH_PER_HEALTH * health.max);
+      healthbar_container.0 = health.max;
+      force_health_update = true;
+    }
+    if force_health_update || healthbar.0 != health.value {
+      hb_node.width = Val::Percent((health.value / health.max) * 100.);
+      healthbar.0 = health.value;
+    }
   }
 }
 
@@ -110,9 +118,36 @@ fn captive_health_update(
 fn init_healthbars(
   mut commands: Commands,
   scene_assets: Res<SceneAssets>,
+  players: Res<Players>,
 ) {
+  for player in 0..players.0 {
+    spawn_healthbars(&mut commands, &scene_assets, player, players.0);
+  }
+}
+
+//player one's in the top left, player two's mirrored over on the right
+fn spawn_healthbars(
+  commands: &mut Commands,
+  scene_assets: &SceneAssets,
+  player: usize,
+  players: usize,
+) {
+  let hud = PlayerHud(player);
+  let (left, right) = if player == 0 {
+    (Val::Px(12.0), Val::Auto)
+  } else {
+    (Val::Auto, Val::Px(12.0))
+  };
+  let label = |text: &str| {
+    if players > 1 {
+      format!("P{} {}", player + 1, text.to_lowercase())
+    } else {
+      text.to_string()
+    }
+  };
 
   commands.spawn((
+    StateScoped(GameState::Playing),
     Node {
       position_type: PositionType::Absolute,
       display: Display::Grid,
@@ -125,7 +160,8 @@ fn init_healthbars(
         GridTrack::auto(),
       ],
       top: Val::Px(12.0),
-      left: Val::Px(12.0),
+      left,
+      right,
       width: Val::Vw(30.0),
       height: Val::Px(80.),
 
@@ -144,7 +180,7 @@ fn init_healthbars(
         padding: UiRect::right(Val::Px(6.0)),
         ..default()
       },
-      Text::new("Health"),
+      Text::new(label("Health")),
       TextFont {
         font: scene_assets.font.clone(),
         font_size: 20.,
@@ -165,6 +201,7 @@ fn init_healthbars(
     .with_children(|parent|{
       parent.spawn((
         HealthBorder(0.),
+        hud,
         Node {
           width: Val::Vw(15.0),
           height: Val::Px(30.),
@@ -177,6 +214,7 @@ fn init_healthbars(
       .with_children(|parent| {
         parent.spawn((
           HealthBar(0.),
+          hud,
           Node {
             margin: UiRect::all(Val::Px(3.)),
             border: UiRect::all(Val::Px(1.)),
@@ -199,8 +237,9 @@ fn init_healthbars(
         ..default()
       },
       CaptiveUi,
+      hud,
       Visibility::Hidden,
-      Text::new("Captive"),
+      Text::new(label("Captive")),
       TextFont {
         font: scene_assets.font.clone(),
         font_size: 20.,
@@ -219,6 +258,7 @@ fn init_healthbars(
 
       parent.spawn((
         CaptiveUi,
+        hud,
         CaptiveHealthBorder(0.),
         Node {
           width: Val::Vw(15.0),
@@ -233,6 +273,7 @@ fn init_healthbars(
       .with_children(|parent| {
         parent.spawn((
           CaptiveHealthBar(0.),
+          hud,
           Node {
             margin: UiRect::all(Val::Px(3.)),
             border: UiRect::all(Val::Px(1.)),
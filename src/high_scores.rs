@@ -0,0 +1,185 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  asset_loader::SceneAssets,
+  menu::{menu_heading, menu_item, menu_root, MenuAction, MenuPage},
+  storage,
+};
+
+pub const HIGH_SCORE_COUNT: usize = 10;
+pub const HIGH_SCORE_NAME_LENGTH: usize = 3;
+const HIGH_SCORES_KEY: &str = "high_scores";
+const HIGH_SCORE_FONT_SIZE: f32 = 22.;
+const HIGH_SCORE_COLOUR: Color = Color::srgb(0.8, 0.8, 0.9);
+const HIGH_SCORE_HIGHLIGHT_COLOUR: Color = Color::srgb(2.0, 1.8, 0.2);
+const SECONDS_PER_DAY: u64 = 86_400;
+
+pub struct HighScoresPlugin;
+
+impl Plugin for HighScoresPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .init_resource::<HighScores>()
+      .add_systems(Startup, load_high_scores)
+      .add_systems(OnEnter(MenuPage::HighScores), spawn_high_scores_page);
+  }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HighScoreEntry {
+  pub name: String,
+  pub score: u64,
+  pub wave: u32,
+  ///seconds since the unix epoch
+  pub date: u64,
+}
+
+impl HighScoreEntry {
+  pub fn new(name: String, score: u64, wave: u32) -> Self {
+    Self {
+      name,
+      score,
+      wave,
+      date: storage::now_secs(),
+    }
+  }
+}
+
+///best scores so far, highest first
+#[derive(Resource, Serialize, Deserialize, Default)]
+pub struct HighScores {
+  pub entries: Vec<HighScoreEntry>,
+}
+
+impl HighScores {
+  ///would this score make it onto the table
+  pub fn qualifies(&self, score: u64) -> bool {
+    score > 0
+      && (self.entries.len() < HIGH_SCORE_COUNT
+        || self.entries.last().is_some_and(|entry| score > entry.score))
+  }
+
+  ///slots the entry in, returning its place on the table if it made it
+  pub fn insert(&mut self, entry: HighScoreEntry) -> Option<usize> {
+    let place = self
+      .entries
+      .iter()
+      .position(|existing| entry.score > existing.score)
+      .unwrap_or(self.entries.len());
+    if place >= HIGH_SCORE_COUNT {
+      return None;
+    }
+    self.entries.insert(place, entry);
+    self.entries.truncate(HIGH_SCORE_COUNT);
+    Some(place)
+  }
+
+  pub fn save(&self) {
+    match serde_json::to_string(self) {
+      Ok(json) => storage::save(HIGH_SCORES_KEY, &json),
+      Err(err) => warn!("failed writing high scores: {:?}", err),
+    }
+  }
+}
+
+fn load_high_scores(mut high_scores: ResMut<HighScores>) {
+  let Some(json) = storage::load(HIGH_SCORES_KEY) else {
+    return;
+  };
+  match serde_json::from_str(&json) {
+    Ok(loaded) => *high_scores = loaded,
+    Err(err) => warn!("failed reading high scores: {:?}", err),
+  }
+}
+
+//days since the epoch to year-month-day, see http://howardhinnant.github.io/date_algorithms.html
+fn format_date(secs: u64) -> String {
+  let days = (secs / SECONDS_PER_DAY) as i64 + 719_468;
+  let era = days.div_euclid(146_097);
+  let day_of_era = days.rem_euclid(146_097);
+  let year_of_era =
+    (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+  let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+  let month_index = (5 * day_of_year + 2) / 153;
+  let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+  let month = if month_index < 10 {
+    month_index + 3
+  } else {
+    month_index - 9
+  };
+  let year = year_of_era + era * 400 + i64::from(month <= 2);
+  format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+///grid of the table, highlighting the entry at `highlight`
+pub fn spawn_high_score_table(
+  parent: &mut ChildSpawnerCommands,
+  high_scores: &HighScores,
+  font: Handle<Font>,
+  highlight: Option<usize>,
+) {
+  if high_scores.entries.is_empty() {
+    parent.spawn(high_score_text(
+      "No high scores yet",
+      font,
+      HIGH_SCORE_COLOUR,
+    ));
+    return;
+  }
+  parent
+    .spawn(Node {
+      display: Display::Grid,
+      grid_template_columns: RepeatedGridTrack::auto(5),
+      column_gap: Val::Px(24.),
+      row_gap: Val::Px(4.),
+      margin: UiRect::bottom(Val::Px(12.)),
+      ..default()
+    })
+    .with_children(|grid| {
+      for (place, entry) in high_scores.entries.iter().enumerate() {
+        let colour = if highlight == Some(place) {
+          HIGH_SCORE_HIGHLIGHT_COLOUR
+        } else {
+          HIGH_SCORE_COLOUR
+        };
+        let cells = [
+          format!("{}.", place + 1),
+          entry.name.clone(),
+          entry.score.to_string(),
+          format!("wave {}", entry.wave),
+          format_date(entry.date),
+        ];
+        for cell in cells {
+          grid.spawn(high_score_text(&cell, font.clone(), colour));
+        }
+      }
+    });
+}
+
+fn high_score_text(text: &str, font: Handle<Font>, colour: Color) -> impl Bundle {
+  (
+    Text::new(text),
+    TextFont {
+      font,
+      font_size: HIGH_SCORE_FONT_SIZE,
+      ..default()
+    },
+    TextColor(colour),
+  )
+}
+
+fn spawn_high_scores_page(
+  mut commands: Commands,
+  scene_assets: Res<SceneAssets>,
+  high_scores: Res<HighScores>,
+) {
+  let font = scene_assets.font.clone();
+  commands
+    .spawn((StateScoped(MenuPage::HighScores), menu_root()))
+    .with_children(|parent| {
+      parent.spawn(menu_heading("High scores", font.clone()));
+      spawn_high_score_table(parent, &high_scores, font.clone(), None);
+      parent.spawn(menu_item(0, "Back", MenuAction::Back, font.clone()));
+    });
+}
@@ -14,7 +14,7 @@ pub struct HitMarkerPlugin;
 impl Plugin for HitMarkerPlugin {
   fn build(&self, app: &mut App) {
     app.add_systems(Startup, init_hit_marker).add_systems(
-      Update,
+      FixedUpdate,
       (apply_hit_marker, update_hit_markers)
         .chain()
         .in_set(GameSchedule::EntityUpdates),
@@ -67,6 +67,7 @@ fn apply_hit_marker(
   for HealthEvent {
     entity,
     health_adjustment,
+    ..
   } in ev_health_reader.read()
   {
     let Ok((mut hit_marker, health)) = query.get_mut(*entity) else {
@@ -3,9 +3,10 @@ use std::f32::consts::PI;
 use bevy::{prelude::*, time::Stopwatch};
 
 use crate::{
-  collision_detection::{Collider, CollisionEvent, Player},
+  collision_detection::{Collider, CollisionEvent, CollisionLayer, Swept},
   movement::{Acceleration, Roller, Velocity},
   scheduling::GameSchedule,
+  ship::PlayerNumber,
 };
 
 const HOOK_RETURN_DISTANCE: f32 = 40.0;
@@ -21,10 +22,10 @@ pub struct HookPlugin;
 impl Plugin for HookPlugin {
   fn build(&self, app: &mut App) {
     app
-      .add_systems(Update, update_hook.in_set(GameSchedule::UserInput))
-      .add_systems(Update, retrieve_hook.in_set(GameSchedule::DespawnEntities))
+      .add_systems(FixedUpdate, update_hook.in_set(GameSchedule::UserInput))
+      .add_systems(FixedUpdate, retrieve_hook.in_set(GameSchedule::DespawnEntities))
       .add_systems(
-        Update,
+        FixedUpdate,
         (apply_collisions, center_hooked).in_set(GameSchedule::EntityUpdates),
       )
       .add_event::<HookReturnedEvent>();
@@ -46,6 +47,11 @@ impl Hookable {
   }
 }
 
+///captured like anything else, but whoever's holding it only gets this fraction of
+///their usual speed
+#[derive(Component)]
+pub struct Heavy(pub f32);
+
 #[derive(Component, Default)]
 pub struct Hooked {
   time: Stopwatch,
@@ -54,7 +60,7 @@ pub struct Hooked {
 }
 
 #[derive(Component)]
-#[require(Acceleration, Velocity, Player)]
+#[require(Acceleration, Velocity, Swept, CollisionLayer = CollisionLayer::Hook)]
 pub struct Hook {
   owner: Entity,
   pub returning: bool,
@@ -72,23 +78,32 @@ impl Hook {
 
 #[derive(Event)]
 pub struct HookReturnedEvent {
+  pub hook: Entity,
+  ///the ship that fired it
+  pub owner: Entity,
   pub target: Option<Entity>,
 }
 
 impl HookReturnedEvent {
-  pub fn new(target: Option<Entity>) -> Self {
-    Self { target }
+  pub fn new(hook: Entity, owner: Entity, target: Option<Entity>) -> Self {
+    Self {
+      hook,
+      owner,
+      target,
+    }
   }
 }
 
 pub fn hook_builder(
   owner: Entity,
+  number: PlayerNumber,
   start: Vec3,
   launcher_veloctiy: Vec3,
   scene: Handle<Scene>,
 ) -> (
   Hook,
-  Player,
+  CollisionLayer,
+  PlayerNumber,
   SceneRoot,
   Velocity,
   Acceleration,
@@ -97,7 +112,8 @@ pub fn hook_builder(
 ) {
   (
     Hook::new(owner),
-    Player,
+    CollisionLayer::Hook,
+    number,
     SceneRoot(scene.clone()),
     Velocity(launcher_veloctiy + Vec3::new(-HOOK_LAUNCH_SPEED, 0., 0.)),
     Acceleration {
@@ -112,6 +128,7 @@ pub fn hook_builder(
 
 fn update_hook(
   mut query: Query<(
+    Entity,
     &mut Hook,
     &GlobalTransform,
     &mut Transform,
@@ -120,42 +137,44 @@ fn update_hook(
   owner_query: Query<&GlobalTransform>,
   mut ev_hook_returned: EventWriter<HookReturnedEvent>,
 ) {
-  let Ok((mut hook, hook_transform, mut transform, mut acceleration)) = query.single_mut()
-  else {
-    return;
-  };
-
-  let Ok(owner_transform) = owner_query.get(hook.owner) else {
-    return;
-  };
-
-  transform.look_at(owner_transform.translation(), Vec3::Y);
-  transform.rotate_local_y(PI * 0.5);
-
-  let diff = hook_transform.translation() - owner_transform.translation();
-  let diff_squared = diff.length_squared();
-  if hook.returning {
-    let acc = diff.normalize() * -HOOK_RETURN_ACCELERATION;
-    acceleration.acceleration = acc;
-    if diff_squared < HOOK_RECLAIM_DISTANCE * HOOK_RECLAIM_DISTANCE {
-      ev_hook_returned.write(HookReturnedEvent::new(hook.target));
+  for (entity, mut hook, hook_transform, mut transform, mut acceleration) in query.iter_mut() {
+    let Ok(owner_transform) = owner_query.get(hook.owner) else {
+      continue;
+    };
+
+    transform.look_at(owner_transform.translation(), Vec3::Y);
+    transform.rotate_local_y(PI * 0.5);
+
+    let diff = hook_transform.translation() - owner_transform.translation();
+    let diff_squared = diff.length_squared();
+    if hook.returning {
+      let acc = diff.normalize() * -HOOK_RETURN_ACCELERATION;
+      acceleration.acceleration = acc;
+      if diff_squared < HOOK_RECLAIM_DISTANCE * HOOK_RECLAIM_DISTANCE {
+        ev_hook_returned.write(HookReturnedEvent::new(entity, hook.owner, hook.target));
+      }
+    } else if diff_squared > HOOK_RETURN_DISTANCE * HOOK_RETURN_DISTANCE {
+      hook.returning = true;
+      //info!("Hook returning");
     }
-  } else if diff_squared > HOOK_RETURN_DISTANCE * HOOK_RETURN_DISTANCE {
-    hook.returning = true;
-    //info!("Hook returning");
   }
 }
 
 fn retrieve_hook(
   mut commands: Commands,
   mut ev_hook_returned: EventReader<HookReturnedEvent>,
-  query: Query<Entity, With<Hook>>,
+  query: Query<(), With<Hook>>,
 ) {
-  for &HookReturnedEvent { target } in ev_hook_returned.read() {
+  for &HookReturnedEvent {
+    hook: entity,
+    target,
+    ..
+  } in ev_hook_returned.read()
+  {
     //despawn our hook
-    let Ok(entity) = query.single() else {
-      return;
-    };
+    if !query.contains(entity) {
+      continue;
+    }
 
     info!("hook returned, captive: {:?}", target);
     if let Some(target) = target {
@@ -165,23 +184,28 @@ fn retrieve_hook(
   }
 }
 
+//something a hook can grab that isn't already on one
+type Catchable = (With<Hookable>, Without<Hook>, Without<Hooked>);
+
 fn apply_collisions(
   mut commands: Commands,
   mut ev_collision: EventReader<CollisionEvent>,
   mut hook_query: Query<(&mut Hook, &GlobalTransform)>,
-  mut target_query: Query<
-    (&mut Transform, &mut Velocity, &GlobalTransform),
-    (With<Hookable>, Without<Hook>),
-  >,
+  mut target_query: Query<(&mut Transform, &mut Velocity, &GlobalTransform), Catchable>,
 ) {
+  //with more than one hook out two can reach the same thing on the same step
+  let mut caught = Vec::new();
   for &CollisionEvent {
-    player: entity,
+    hitter: entity,
     other: collided,
   } in ev_collision.read()
   {
     let Ok((mut hook, hook_transform)) = hook_query.get_mut(entity) else {
       continue;
     };
+    if hook.target.is_some() || caught.contains(&collided) {
+      continue;
+    }
     let Ok((mut transform, mut target_velocity, target_transform)) = target_query.get_mut(collided)
     else {
       continue;
@@ -190,6 +214,7 @@ fn apply_collisions(
     //target_acceleration.acceleration = Vec3::ZERO;
     hook.returning = true;
     hook.target = Some(collided);
+    caught.push(collided);
     commands
       .entity(entity)
       .remove::<Collider>()
@@ -205,12 +230,11 @@ fn apply_collisions(
 }
 
 fn center_hooked(mut query: Query<(&mut Hooked, &mut Transform, &Hookable)>, time: Res<Time>) {
-  let Ok((mut hooked, mut transform, hookable)) = query.single_mut() else {
-    return;
-  };
-  hooked.time.tick(time.delta());
-  let ratio = (hooked.time.elapsed_secs() * HOOK_CENTERING_SPEED).clamp(0.0, 1.0);
-
-  transform.translation = hooked.initial_position.lerp(hookable.translation, ratio);
-  transform.rotation = hooked.initial_rotation.lerp(hookable.rotation, ratio);
+  for (mut hooked, mut transform, hookable) in query.iter_mut() {
+    hooked.time.tick(time.delta());
+    let ratio = (hooked.time.elapsed_secs() * HOOK_CENTERING_SPEED).clamp(0.0, 1.0);
+
+    transform.translation = hooked.initial_position.lerp(hookable.translation, ratio);
+    transform.rotation = hooked.initial_rotation.lerp(hookable.rotation, ratio);
+  }
 }
@@ -1,6 +1,13 @@
 use bevy::{prelude::*, window::PrimaryWindow};
 
-use crate::scheduling::GameSchedule;
+use crate::{
+  bindings::{rebinding, Bindings, BoundButtons, ControlAction, PlayerPads},
+  game_manager::{Players, MAX_PLAYERS},
+  scheduling::GameSchedule,
+};
+
+//how far the shoot finger moves before it's a swipe rather than a tap, in pixels
+const TOUCH_SWIPE_DISTANCE: f32 = 60.;
 
 #[derive(PartialEq)]
 pub enum InputEventType {
@@ -8,9 +15,16 @@ pub enum InputEventType {
   Released,
 }
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone, Copy)]
 pub enum InputEventAction {
+  ///launches the hook, or calls it back when it's already out
   Shoot,
+  Recall,
+  ///throws the captive forward at the enemies
+  Jettison,
+  ///eats the captive for health
+  Consume,
+  Pause,
 }
 
 pub struct GameInputPlugin;
@@ -20,24 +34,49 @@ impl Plugin for GameInputPlugin {
     app
       .add_event::<InputMovementEvent>()
       .add_event::<InputTriggerEvent>()
+      .init_resource::<PlayerControls>()
+      .init_resource::<OnScreenControls>()
       .add_systems(Startup, init_input_resources)
       .add_systems(
         Update,
-        (read_keys, read_mouse, read_touch, read_gamepads)
+        (
+          //buttons go to the rebinding page while it's waiting for one
+          (
+            assign_pads,
+            read_buttons,
+            read_mouse,
+            read_touch,
+            read_gamepads,
+          )
+            .chain()
+            .run_if(not(rebinding)),
+          gather_controls,
+        )
           .chain()
-          .in_set(GameSchedule::UserInput),
-      );
+          .in_set(GameSchedule::ReadInput),
+      )
+      .add_systems(FixedPostUpdate, clear_controls);
   }
 }
 
+///which way to go, a length of 1 is full thrust and analog sticks give less
 #[derive(Event)]
 pub struct InputMovementEvent {
   pub direction: Vec2,
+  ///who's steering, player one unless it came from another player's pad
+  pub player: usize,
 }
 
 impl InputMovementEvent {
   pub fn new(direction: Vec2) -> Self {
-    Self { direction }
+    Self {
+      direction,
+      player: 0,
+    }
+  }
+
+  pub fn for_player(self, player: usize) -> Self {
+    Self { player, ..self }
   }
 }
 
@@ -45,14 +84,58 @@ impl InputMovementEvent {
 pub struct InputTriggerEvent {
   pub action: InputEventAction,
   pub input_type: InputEventType,
+  pub player: usize,
 }
 
 impl InputTriggerEvent {
   pub fn new(action: InputEventAction, input_type: InputEventType) -> Self {
-    Self { action, input_type }
+    Self {
+      action,
+      input_type,
+      player: 0,
+    }
+  }
+
+  pub fn for_player(self, player: usize) -> Self {
+    Self { player, ..self }
+  }
+}
+
+///one player's controls as the simulation sees them
+#[derive(Default, Clone, Copy, PartialEq)]
+pub struct ControlInput {
+  pub movement: Vec2,
+  ///buttons pressed since the last step, only the first step to see them acts on them
+  pub shoot: bool,
+  pub recall: bool,
+  pub jettison: bool,
+  pub consume: bool,
+}
+
+///everyone's controls, gathered from the input events each frame
+///and read by every fixed step until the next frame replaces them
+#[derive(Resource, Default)]
+pub struct PlayerControls(pub [ControlInput; MAX_PLAYERS]);
+
+impl PlayerControls {
+  pub fn get(&self, player: usize) -> ControlInput {
+    self.0.get(player).copied().unwrap_or_default()
   }
 }
 
+//bound buttons and the trigger each sends
+const TRIGGERS: [(ControlAction, InputEventAction); 5] = [
+  (ControlAction::Shoot, InputEventAction::Shoot),
+  (ControlAction::Recall, InputEventAction::Recall),
+  (ControlAction::Jettison, InputEventAction::Jettison),
+  (ControlAction::Consume, InputEventAction::Consume),
+  (ControlAction::Pause, InputEventAction::Pause),
+];
+
+///touches are going to the on screen stick and buttons instead of finger gestures
+#[derive(Resource, Default)]
+pub struct OnScreenControls(pub bool);
+
 #[derive(Resource)]
 struct MouseResource {
   last: Vec2,
@@ -72,28 +155,38 @@ fn init_input_resources(mut commands: Commands) {
   });
 }
 
+//a pad joins the first time any of its buttons are pressed
+fn assign_pads(mut pads: ResMut<PlayerPads>, gamepads: Query<(Entity, &Gamepad)>) {
+  //forget any that have been unplugged
+  pads.0.retain(|pad| gamepads.contains(*pad));
+  for (entity, gamepad) in gamepads.iter() {
+    if !pads.0.contains(&entity) && gamepad.get_just_pressed().next().is_some() {
+      info!("gamepad {:?} assigned as pad {}", entity, pads.0.len() + 1);
+      pads.0.push(entity);
+    }
+  }
+}
+
+//either stick flies the ship, as hard as it's pushed
 fn read_gamepads(
+  pads: Res<PlayerPads>,
+  players: Res<Players>,
   gamepads: Query<&Gamepad>,
+  bindings: Res<Bindings>,
   mut ev_movement_event: EventWriter<InputMovementEvent>,
-  mut ev_trigger_event: EventWriter<InputTriggerEvent>,
 ) {
-  for gamepad in &gamepads {
-    if gamepad.just_pressed(GamepadButton::South) {
-      ev_trigger_event.write(InputTriggerEvent::new(
-        InputEventAction::Shoot,
-        InputEventType::Pressed,
-      ));
-    } else if gamepad.just_released(GamepadButton::South) {
-      ev_trigger_event.write(InputTriggerEvent::new(
-        InputEventAction::Shoot,
-        InputEventType::Released,
-      ));
-    }
-    let left_stick_x = gamepad.get(GamepadAxis::LeftStickX).unwrap();
-    let left_stick_y = gamepad.get(GamepadAxis::LeftStickY).unwrap();
-    let dir: Vec2 = Vec2::new(-left_stick_x, left_stick_y);
-    if dir.length_squared() > 0.1 {
-      ev_movement_event.write(InputMovementEvent::new(dir));
+  for player in 0..players.0 {
+    let Some(gamepad) = pads
+      .pad(player, players.0)
+      .and_then(|pad| gamepads.get(pad).ok())
+    else {
+      continue;
+    };
+    let stick =
+      bindings.stick.apply(gamepad.left_stick()) + bindings.stick.apply(gamepad.right_stick());
+    if stick != Vec2::ZERO {
+      ev_movement_event
+        .write(InputMovementEvent::new(Vec2::new(-stick.x, stick.y)).for_player(player));
     }
   }
 }
@@ -103,7 +196,12 @@ fn read_touch(
   mut ev_movement_event: EventWriter<InputMovementEvent>,
   mut ev_trigger_event: EventWriter<InputTriggerEvent>,
   mut touch_tracker: ResMut<TouchResource>,
+  on_screen: Res<OnScreenControls>,
 ) {
+  if on_screen.0 {
+    touch_tracker.move_finger = None;
+    return;
+  }
   for touch in touches.iter_just_pressed() {
     //fisrt touch down is our move finger
     //info!("touch down: {:?}", touch.id());
@@ -130,6 +228,16 @@ fn read_touch(
         InputEventAction::Shoot,
         InputEventType::Released,
       ));
+      //a swipe with the shoot finger throws the captive up or eats it down
+      let swipe = touch.position() - touch.start_position();
+      if swipe.length() > TOUCH_SWIPE_DISTANCE {
+        let action = if swipe.y < 0. {
+          InputEventAction::Jettison
+        } else {
+          InputEventAction::Consume
+        };
+        ev_trigger_event.write(InputTriggerEvent::new(action, InputEventType::Pressed));
+      }
     }
   }
 
@@ -152,26 +260,13 @@ fn read_touch(
   }
 }
 
+//left button drags the ship about, the other buttons go through the bindings
 fn read_mouse(
   buttons: Res<ButtonInput<MouseButton>>,
   window: Single<&Window, With<PrimaryWindow>>,
   mut ev_movement_event: EventWriter<InputMovementEvent>,
-  mut ev_trigger_event: EventWriter<InputTriggerEvent>,
   mut mouse_location: ResMut<MouseResource>,
 ) {
-  if buttons.just_pressed(MouseButton::Right) {
-    ev_trigger_event.write(InputTriggerEvent::new(
-      InputEventAction::Shoot,
-      InputEventType::Pressed,
-    ));
-  }
-  if buttons.just_released(MouseButton::Right) {
-    ev_trigger_event.write(InputTriggerEvent::new(
-      InputEventAction::Shoot,
-      InputEventType::Released,
-    ));
-  }
-
   if buttons.pressed(MouseButton::Left) {
     if let Some(pos) = window.cursor_position() {
       if buttons.just_pressed(MouseButton::Left) {
@@ -187,39 +282,85 @@ fn read_mouse(
   }
 }
 
-fn read_keys(
-  keyboard_input: Res<ButtonInput<KeyCode>>,
+//keys and buttons on every device, through whatever they're bound to
+fn read_buttons(
+  buttons: BoundButtons,
+  players: Res<Players>,
   mut ev_movement_event: EventWriter<InputMovementEvent>,
   mut ev_trigger_event: EventWriter<InputTriggerEvent>,
 ) {
-  let mut dir: Vec2 = Vec2::ZERO;
-  if keyboard_input.pressed(KeyCode::KeyD) {
-    dir.x -= 1.;
-  }
-  if keyboard_input.pressed(KeyCode::KeyA) {
-    dir.x += 1.;
-  }
-  if keyboard_input.pressed(KeyCode::KeyW) {
-    dir.y += 1.;
+  for player in 0..players.0 {
+    let mut dir: Vec2 = Vec2::ZERO;
+    if buttons.pressed(player, ControlAction::MoveRight) {
+      dir.x -= 1.;
+    }
+    if buttons.pressed(player, ControlAction::MoveLeft) {
+      dir.x += 1.;
+    }
+    if buttons.pressed(player, ControlAction::MoveUp) {
+      dir.y += 1.;
+    }
+    if buttons.pressed(player, ControlAction::MoveDown) {
+      dir.y -= 1.;
+    }
+    if dir != Vec2::ZERO {
+      ev_movement_event.write(InputMovementEvent::new(dir).for_player(player));
+    }
+
+    for (control, action) in TRIGGERS {
+      if buttons.just_pressed(player, control) {
+        ev_trigger_event
+          .write(InputTriggerEvent::new(action, InputEventType::Pressed).for_player(player));
+      }
+      if buttons.just_released(player, control) {
+        ev_trigger_event
+          .write(InputTriggerEvent::new(action, InputEventType::Released).for_player(player));
+      }
+    }
   }
-  if keyboard_input.pressed(KeyCode::KeyS) {
-    dir.y -= 1.;
+}
+
+///turns the frame's input events into the controls the simulation reads
+pub fn gather_controls(
+  mut ev_movement_reader: EventReader<InputMovementEvent>,
+  mut ev_trigger_reader: EventReader<InputTriggerEvent>,
+  mut controls: ResMut<PlayerControls>,
+) {
+  for controls in controls.0.iter_mut() {
+    controls.movement = Vec2::ZERO;
   }
-  if dir != Vec2::ZERO {
-    ev_movement_event.write(InputMovementEvent::new(dir));
+  for InputMovementEvent { direction, player } in ev_movement_reader.read() {
+    if let Some(controls) = controls.0.get_mut(*player) {
+      controls.movement += *direction;
+    }
   }
-
-  if keyboard_input.just_pressed(KeyCode::Space) {
-    ev_trigger_event.write(InputTriggerEvent::new(
-      InputEventAction::Shoot,
-      InputEventType::Pressed,
-    ));
+  for InputTriggerEvent {
+    action,
+    input_type,
+    player,
+  } in ev_trigger_reader.read()
+  {
+    let Some(controls) = controls.0.get_mut(*player) else {
+      continue;
+    };
+    if *input_type != InputEventType::Pressed {
+      continue;
+    }
+    match action {
+      InputEventAction::Shoot => controls.shoot = true,
+      InputEventAction::Recall => controls.recall = true,
+      InputEventAction::Jettison => controls.jettison = true,
+      InputEventAction::Consume => controls.consume = true,
+      InputEventAction::Pause => {}
+    }
   }
+}
 
-  if keyboard_input.just_released(KeyCode::Space) {
-    ev_trigger_event.write(InputTriggerEvent::new(
-      InputEventAction::Shoot,
-      InputEventType::Released,
-    ));
+fn clear_controls(mut controls: ResMut<PlayerControls>) {
+  for controls in controls.0.iter_mut() {
+    *controls = ControlInput {
+      movement: controls.movement,
+      ..default()
+    };
   }
 }
@@ -0,0 +1,91 @@
+use bevy::{app::RunFixedMainLoopSystem, prelude::*};
+
+pub struct InterpolationPlugin;
+
+impl Plugin for InterpolationPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .add_systems(
+        FixedFirst,
+        restore_transforms.before(TransformSystem::TransformPropagate),
+      )
+      .add_systems(FixedLast, store_transforms)
+      .add_systems(
+        RunFixedMainLoop,
+        interpolate_transforms.in_set(RunFixedMainLoopSystem::AfterFixedMainLoop),
+      );
+  }
+}
+
+#[derive(Clone, Copy)]
+struct Pose {
+  translation: Vec3,
+  rotation: Quat,
+}
+
+impl Pose {
+  fn new(transform: &Transform) -> Self {
+    Self {
+      translation: transform.translation,
+      rotation: transform.rotation,
+    }
+  }
+
+  fn lerp(&self, other: &Pose, t: f32) -> Self {
+    Self {
+      translation: self.translation.lerp(other.translation, t),
+      rotation: self.rotation.slerp(other.rotation, t),
+    }
+  }
+
+  fn apply(&self, transform: &mut Transform) {
+    transform.translation = self.translation;
+    transform.rotation = self.rotation;
+  }
+}
+
+///moved by the simulation, drawn part way between its last two fixed steps
+#[derive(Component, Default)]
+pub struct Interpolated {
+  //previous and current step, none until it's been through one
+  steps: Option<(Pose, Pose)>,
+}
+
+//put things back where the simulation left them before stepping again
+fn restore_transforms(mut query: Query<(&mut Transform, &Interpolated)>) {
+  for (mut transform, interpolated) in query.iter_mut() {
+    if let Some((_, current)) = &interpolated.steps {
+      current.apply(&mut transform);
+    }
+  }
+}
+
+fn store_transforms(
+  mut query: Query<(Entity, &mut Interpolated, &Transform, Option<Ref<ChildOf>>)>,
+  mut orphaned: RemovedComponents<ChildOf>,
+) {
+  let orphaned: Vec<Entity> = orphaned.read().collect();
+  for (entity, mut interpolated, transform, child_of) in query.iter_mut() {
+    let now = Pose::new(transform);
+    //a new parent or none at all means a new space, don't blend across it
+    let reparented =
+      child_of.is_some_and(|child_of| child_of.is_changed()) || orphaned.contains(&entity);
+    let previous = match interpolated.steps {
+      Some((_, current)) if !reparented => current,
+      _ => now,
+    };
+    interpolated.steps = Some((previous, now));
+  }
+}
+
+fn interpolate_transforms(
+  mut query: Query<(&mut Transform, &Interpolated)>,
+  time: Res<Time<Fixed>>,
+) {
+  let t = time.overstep_fraction();
+  for (mut transform, interpolated) in query.iter_mut() {
+    if let Some((previous, current)) = &interpolated.steps {
+      previous.lerp(current, t).apply(&mut transform);
+    }
+  }
+}
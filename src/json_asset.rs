@@ -0,0 +1,58 @@
+use std::marker::PhantomData;
+
+use bevy::{
+  asset::{io::Reader, AssetLoader, LoadContext},
+  prelude::*,
+};
+use serde::de::DeserializeOwned;
+
+///data assets authored as json under assets/data
+pub trait JsonAsset: Asset + DeserializeOwned {
+  const EXTENSIONS: &'static [&'static str];
+
+  ///chance to queue up any other assets this one refers to
+  fn load_dependencies(&mut self, _load_context: &mut LoadContext) {}
+}
+
+pub struct JsonAssetLoader<A>(PhantomData<fn() -> A>);
+
+impl<A> Default for JsonAssetLoader<A> {
+  fn default() -> Self {
+    Self(PhantomData)
+  }
+}
+
+impl<A: JsonAsset> AssetLoader for JsonAssetLoader<A> {
+  type Asset = A;
+  type Settings = ();
+  type Error = Box<dyn std::error::Error + Send + Sync>;
+
+  async fn load(
+    &self,
+    reader: &mut dyn Reader,
+    _settings: &(),
+    load_context: &mut LoadContext<'_>,
+  ) -> Result<Self::Asset, Self::Error> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes).await?;
+    let mut asset: A = serde_json::from_slice(&bytes)?;
+    asset.load_dependencies(load_context);
+    Ok(asset)
+  }
+
+  fn extensions(&self) -> &[&str] {
+    A::EXTENSIONS
+  }
+}
+
+pub trait JsonAssetApp {
+  fn init_json_asset<A: JsonAsset>(&mut self) -> &mut Self;
+}
+
+impl JsonAssetApp for App {
+  fn init_json_asset<A: JsonAsset>(&mut self) -> &mut Self {
+    self
+      .init_asset::<A>()
+      .register_asset_loader(JsonAssetLoader::<A>::default())
+  }
+}
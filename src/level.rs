@@ -0,0 +1,313 @@
+use bevy::{asset::LoadContext, prelude::*};
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::{
+  asset_loader::AssetsLoading,
+  enemy::{Enemy, EnemyKind, SpawnEnemyEvent, ENEMY_START_POINT_X, ENEMY_START_POINT_Z_BOUNDS_MAX},
+  game_manager::Game,
+  json_asset::{JsonAsset, JsonAssetApp},
+  rng::GameRng,
+  scheduling::GameSchedule,
+  ship::{Captured, Jettisoned},
+  state::GameState,
+};
+
+const FIRST_LEVEL_PATH: &str = "data/levels/stage1.level.json";
+//breather between one wave finishing and the next starting
+const LEVEL_WAVE_BREAK_SECONDS: f32 = 2.0;
+//give the last spawns a moment to appear before checking the wave is cleared
+const LEVEL_WAVE_SETTLE_SECONDS: f32 = 0.5;
+
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .init_json_asset::<LevelData>()
+      .init_resource::<LevelProgress>()
+      .add_systems(Startup, load_levels)
+      .add_systems(OnEnter(GameState::Playing), start_levels)
+      .add_systems(
+        FixedUpdate,
+        (spawn_wave_enemies, check_wave_complete)
+          .chain()
+          .in_set(GameSchedule::EntityUpdates),
+      );
+  }
+}
+
+///a stage, made up of waves played one after another
+#[derive(Asset, TypePath, Deserialize)]
+pub struct LevelData {
+  pub name: String,
+  pub waves: Vec<WaveData>,
+  ///file of the stage to play after this one, relative to this file,
+  ///when missing we loop back round to the first stage
+  #[serde(default)]
+  next: Option<String>,
+  #[serde(skip)]
+  #[dependency]
+  next_level: Option<Handle<LevelData>>,
+  ///looping track played through the stage, relative to this file or from the assets root with a leading /
+  #[serde(default)]
+  music: Option<String>,
+  #[serde(skip)]
+  #[dependency]
+  pub music_track: Option<Handle<AudioSource>>,
+}
+
+impl JsonAsset for LevelData {
+  const EXTENSIONS: &'static [&'static str] = &["level.json"];
+
+  fn load_dependencies(&mut self, load_context: &mut LoadContext) {
+    if let Some(next) = &self.next {
+      match load_context.asset_path().resolve_embed(next) {
+        Ok(path) => self.next_level = Some(load_context.load(path)),
+        Err(err) => warn!("bad next level path {:?}: {:?}", next, err),
+      }
+    }
+    if let Some(music) = &self.music {
+      match load_context.asset_path().resolve_embed(music) {
+        Ok(path) => self.music_track = Some(load_context.load(path)),
+        Err(err) => warn!("bad music path {:?}: {:?}", music, err),
+      }
+    }
+  }
+}
+
+#[derive(Deserialize)]
+pub struct WaveData {
+  pub spawns: Vec<SpawnData>,
+  #[serde(default)]
+  pub complete: WaveComplete,
+}
+
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WaveComplete {
+  ///every enemy spawned by the wave has been destroyed or left the screen
+  #[default]
+  Cleared,
+  ///move on after a fixed time, whatever is left on screen
+  Time { seconds: f32 },
+}
+
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum Formation {
+  ///one after another down the same path
+  #[default]
+  Column,
+  ///side by side, spread along z
+  Line,
+  ///spread along z with the middle leading
+  Vee,
+  ///one after another from random heights
+  Random,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct SpawnData {
+  ///seconds from the start of the wave
+  #[serde(default)]
+  pub time: f32,
+  pub enemy: EnemyKind,
+  ///entry height, random when missing
+  #[serde(default)]
+  pub z: Option<f32>,
+  #[serde(default)]
+  pub x: Option<f32>,
+  ///[x, z], enemies use their own entry speed when missing
+  #[serde(default)]
+  pub velocity: Option<[f32; 2]>,
+  #[serde(default)]
+  pub formation: Formation,
+  #[serde(default = "default_count")]
+  pub count: u32,
+  ///gap along z for line and vee formations
+  #[serde(default = "default_spacing")]
+  pub spacing: f32,
+  ///seconds between members of column, vee and random formations
+  #[serde(default = "default_interval")]
+  pub interval: f32,
+}
+
+fn default_count() -> u32 {
+  1
+}
+
+fn default_spacing() -> f32 {
+  6.0
+}
+
+fn default_interval() -> f32 {
+  0.5
+}
+
+struct PendingSpawn {
+  time: f32,
+  kind: EnemyKind,
+  translation: Vec3,
+  velocity: Option<Vec3>,
+}
+
+#[derive(Resource, Default)]
+struct LevelAssets {
+  first: Handle<LevelData>,
+}
+
+#[derive(Resource, Default)]
+pub struct LevelProgress {
+  level: Handle<LevelData>,
+  started: bool,
+  wave: usize,
+  wave_time: f32,
+  last_spawn_time: f32,
+  //waiting to go, latest first
+  pending: Vec<PendingSpawn>,
+}
+
+impl LevelProgress {
+  ///stage currently being played
+  pub fn level(&self) -> &Handle<LevelData> {
+    &self.level
+  }
+}
+
+fn load_levels(
+  mut commands: Commands,
+  asset_server: Res<AssetServer>,
+  mut loading: ResMut<AssetsLoading>,
+) {
+  let first = asset_server.load(FIRST_LEVEL_PATH);
+  loading.0.push(first.clone().untyped());
+  commands.insert_resource(LevelAssets { first });
+}
+
+fn start_levels(level_assets: Res<LevelAssets>, mut progress: ResMut<LevelProgress>) {
+  progress.level = level_assets.first.clone();
+  progress.started = false;
+}
+
+fn start_wave(
+  progress: &mut LevelProgress,
+  wave: usize,
+  levels: &Assets<LevelData>,
+  game: &mut Game,
+  rng: &mut GameRng,
+) {
+  progress.wave = wave;
+  progress.wave_time = -LEVEL_WAVE_BREAK_SECONDS;
+  progress.pending.clear();
+  let Some(level) = levels.get(&progress.level) else {
+    return;
+  };
+  let Some(wave_data) = level.waves.get(wave) else {
+    return;
+  };
+  game.wave += 1;
+  info!("{} wave {} (total {})", level.name, wave + 1, game.wave);
+
+  for spawn in wave_data.spawns.iter() {
+    let velocity = spawn.velocity.map(|[x, z]| Vec3::new(x, 0., z));
+    let x = spawn.x.unwrap_or(ENEMY_START_POINT_X);
+    let mid = (spawn.count as f32 - 1.) * 0.5;
+    let base_z = spawn
+      .z
+      .unwrap_or_else(|| rng.gen_range(-1. ..1.) * ENEMY_START_POINT_Z_BOUNDS_MAX);
+    for i in 0..spawn.count {
+      let offset = i as f32 - mid;
+      let (z, delay) = match spawn.formation {
+        Formation::Column => (base_z, i as f32 * spawn.interval),
+        Formation::Line => (base_z + offset * spawn.spacing, 0.),
+        Formation::Vee => (
+          base_z + offset * spawn.spacing,
+          offset.abs() * spawn.interval,
+        ),
+        Formation::Random => (
+          rng.gen_range(-1. ..1.) * ENEMY_START_POINT_Z_BOUNDS_MAX,
+          i as f32 * spawn.interval,
+        ),
+      };
+      progress.pending.push(PendingSpawn {
+        time: spawn.time + delay,
+        kind: spawn.enemy,
+        translation: Vec3::new(x, 0., z),
+        velocity,
+      });
+    }
+  }
+  progress.pending.sort_by(|a, b| b.time.total_cmp(&a.time));
+  progress.last_spawn_time = progress.pending.first().map_or(0., |spawn| spawn.time);
+}
+
+fn spawn_wave_enemies(
+  mut progress: ResMut<LevelProgress>,
+  time: Res<Time>,
+  mut ev_spawn_enemy_writer: EventWriter<SpawnEnemyEvent>,
+) {
+  progress.wave_time += time.delta_secs();
+  while progress
+    .pending
+    .last()
+    .is_some_and(|spawn| spawn.time <= progress.wave_time)
+  {
+    let Some(spawn) = progress.pending.pop() else {
+      break;
+    };
+    ev_spawn_enemy_writer.write(SpawnEnemyEvent::new(
+      spawn.kind,
+      spawn.translation,
+      spawn.velocity,
+    ));
+  }
+}
+
+//captives are on our side, and thrown ones are on their way out
+type LiveEnemies = (With<Enemy>, Without<Captured>, Without<Jettisoned>);
+
+fn check_wave_complete(
+  mut progress: ResMut<LevelProgress>,
+  levels: Res<Assets<LevelData>>,
+  level_assets: Res<LevelAssets>,
+  enemy_query: Query<(), LiveEnemies>,
+  mut game: Single<&mut Game>,
+  mut rng: ResMut<GameRng>,
+) {
+  if !progress.started {
+    progress.started = true;
+    start_wave(&mut progress, 0, &levels, &mut game, &mut rng);
+    return;
+  }
+  if !progress.pending.is_empty()
+    || progress.wave_time < progress.last_spawn_time + LEVEL_WAVE_SETTLE_SECONDS
+  {
+    return;
+  }
+  let Some(level) = levels.get(&progress.level) else {
+    return;
+  };
+  let complete = match level.waves.get(progress.wave) {
+    Some(wave) => match wave.complete {
+      WaveComplete::Cleared => enemy_query.is_empty(),
+      WaveComplete::Time { seconds } => progress.wave_time >= seconds,
+    },
+    None => true,
+  };
+  if !complete {
+    return;
+  }
+
+  let next_wave = progress.wave + 1;
+  if next_wave < level.waves.len() {
+    start_wave(&mut progress, next_wave, &levels, &mut game, &mut rng);
+    return;
+  }
+  //on to the next stage, or round again from the top
+  progress.level = level
+    .next_level
+    .clone()
+    .unwrap_or_else(|| level_assets.first.clone());
+  start_wave(&mut progress, 0, &levels, &mut game, &mut rng);
+}
@@ -1,55 +1,99 @@
 mod asset_loader;
+mod audio;
+mod bindings;
 mod bounds_check;
 mod bullet;
 mod camera;
+mod collider_shape;
 mod collision_detection;
+mod combo;
+mod controls;
 mod constants;
+mod death;
 mod enemy;
 mod game_manager;
+mod game_over;
 
 mod health;
+mod high_scores;
 mod hit_marker;
 mod hook;
 mod input;
+mod interpolation;
+mod json_asset;
+mod level;
+mod menu;
 mod movement;
+mod pause;
+mod projectile;
+mod replay;
+mod rng;
 mod scheduling;
+mod settings;
 mod ship;
 mod sidewinder;
+mod spatial_grid;
 mod state;
+mod storage;
+mod touch_controls;
+mod weapon;
 mod wreck;
 mod game_ui;
 mod health_bars;
 mod effect_sprite;
+#[cfg(any(test, feature = "headless"))]
+pub mod headless;
 
 mod dropship;
 mod mine;
 mod actions;
+mod ai;
 
 use actions::ActionPlugin;
+use ai::AiPlugin;
 use asset_loader::AssetLoaderPlugin;
-use bevy::{asset::AssetMetaCheck, prelude::*, window::WindowCloseRequested};
+use audio::GameAudioPlugin;
+use bevy::{
+  app::PluginGroupBuilder, asset::AssetMetaCheck, prelude::*, window::WindowCloseRequested,
+};
+use bindings::BindingsPlugin;
 use bounds_check::BoundsCheckPlugin;
 use bullet::BulletPlugin;
 use camera::CameraPlugin;
 use collision_detection::CollsionDetectionPlugin;
+use death::DeathPlugin;
+use combo::ComboPlugin;
+use controls::ControlsPlugin;
 use dropship::DropshipPlugin;
 use effect_sprite::EffectSpritePlugin;
 use enemy::EnemyPlugin;
 use game_manager::GameManagerPlugin;
+use game_over::GameOverPlugin;
 
 use game_ui::GameUiPlugin;
 use health::HealthPlugin;
 use health_bars::HealthBarsPlugin;
+use high_scores::HighScoresPlugin;
 use hit_marker::HitMarkerPlugin;
 use hook::HookPlugin;
 
 use input::GameInputPlugin;
+use interpolation::InterpolationPlugin;
+use level::LevelPlugin;
+use menu::MenuPlugin;
 use mine::MinePlugin;
 use movement::MovementPlugin;
+use pause::PausePlugin;
+use projectile::ProjectilePlugin;
+use replay::ReplayPlugin;
+use rng::RngPlugin;
 use scheduling::SchedulingPlugin;
+use settings::SettingsPlugin;
 use ship::ShipPlugin;
 use sidewinder::SidewinderPlugin;
 use state::{GameState, GameStateEvent, StatePlugin};
+use touch_controls::TouchControlsPlugin;
+use weapon::WeaponPlugin;
 use wreck::WreckPlugin;
 
 
@@ -87,40 +131,66 @@ pub fn run_game() {
           ..default()
         }),
     )
+    .add_plugins(GameplayPlugins)
     .add_plugins((
-      StatePlugin,
-      SchedulingPlugin,
       CameraPlugin,
       AssetLoaderPlugin,
-      MovementPlugin,
-      ShipPlugin,
-      CollsionDetectionPlugin,
-      BulletPlugin,
-      EnemyPlugin,
-      SidewinderPlugin,
-      BoundsCheckPlugin,
-      HookPlugin,
       //SplosionPlugin,
-      WreckPlugin,
       EffectSpritePlugin,
-    ))
-    .add_plugins((
-      GameInputPlugin,
-      GameManagerPlugin,
-      HealthPlugin,
-      HitMarkerPlugin,
       GameUiPlugin,
       HealthBarsPlugin,
-      //AiPlugin,
-      DropshipPlugin,
-      MinePlugin,
-      ActionPlugin,
+      MenuPlugin,
+      GameOverPlugin,
+      PausePlugin,
+      SettingsPlugin,
+      ControlsPlugin,
+      TouchControlsPlugin,
+      HighScoresPlugin,
+      GameAudioPlugin,
+      ReplayPlugin,
     ))
     //.add_systems(Update, make_visible.run_if(in_state(GameState::Loading)))
     .add_systems(PreUpdate, check_window)
     .run();
 }
 
+///everything that plays the game, without the window, rendering, audio or menus,
+///so it can also be run headless
+struct GameplayPlugins;
+
+impl PluginGroup for GameplayPlugins {
+  fn build(self) -> PluginGroupBuilder {
+    PluginGroupBuilder::start::<Self>()
+      .add(StatePlugin)
+      .add(SchedulingPlugin)
+      .add(RngPlugin)
+      .add(InterpolationPlugin)
+      .add(MovementPlugin)
+      .add(ShipPlugin)
+      .add(CollsionDetectionPlugin)
+      .add(BulletPlugin)
+      .add(ProjectilePlugin)
+      .add(EnemyPlugin)
+      .add(SidewinderPlugin)
+      .add(WeaponPlugin)
+      .add(BoundsCheckPlugin)
+      .add(HookPlugin)
+      .add(WreckPlugin)
+      .add(BindingsPlugin)
+      .add(GameInputPlugin)
+      .add(GameManagerPlugin)
+      .add(HealthPlugin)
+      .add(HitMarkerPlugin)
+      .add(AiPlugin)
+      .add(DropshipPlugin)
+      .add(MinePlugin)
+      .add(ActionPlugin)
+      .add(LevelPlugin)
+      .add(ComboPlugin)
+      .add(DeathPlugin)
+  }
+}
+
 fn check_window(
   mut ev_windows_close_reader: EventReader<WindowCloseRequested>,
   mut ev_game_state_writer: EventWriter<GameStateEvent>,
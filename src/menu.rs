@@ -0,0 +1,353 @@
+use bevy::prelude::*;
+
+use crate::{
+  asset_loader::SceneAssets,
+  bindings::ControlAction,
+  game_manager::{Players, MAX_PLAYERS},
+  input::{InputEventAction, InputEventType, InputMovementEvent, InputTriggerEvent},
+  replay::Replays,
+  scheduling::GameSchedule,
+  state::{GameState, GameStateEvent},
+  APP_NAME,
+};
+
+const MENU_TITLE_FONT_SIZE: f32 = 60.;
+const MENU_HEADING_FONT_SIZE: f32 = 40.;
+const MENU_ITEM_FONT_SIZE: f32 = 30.;
+const MENU_ITEM_COLOUR: Color = Color::srgb(0.6, 0.6, 0.7);
+const MENU_SELECTED_COLOUR: Color = Color::srgb(2.0, 1.8, 0.2);
+const MENU_BACKGROUND_COLOUR: Color = Color::srgba(0.05, 0.0, 0.1, 0.6);
+//how far a stick / key has to be pushed to move the selection
+const MENU_NAVIGATE_THRESHOLD: f32 = 0.5;
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .add_sub_state::<MenuPage>()
+      .init_resource::<MenuSelection>()
+      .add_event::<MenuActionEvent>()
+      .add_event::<MenuAdjustEvent>()
+      .add_systems(OnEnter(MenuPage::Title), spawn_title_screen)
+      .add_systems(
+        Update,
+        (
+          reset_selection,
+          input_navigation,
+          pointer_navigation,
+          highlight_selection,
+        )
+          .chain()
+          .after(GameSchedule::ReadInput),
+      )
+      .add_systems(
+        Update,
+        (menu_actions, menu_back)
+          .after(pointer_navigation)
+          .run_if(in_state(GameState::Menu)),
+      );
+  }
+}
+
+#[derive(SubStates, Debug, Clone, PartialEq, Eq, Hash, Default, Copy)]
+#[source(GameState = GameState::Menu)]
+#[states(scoped_entities)]
+pub enum MenuPage {
+  #[default]
+  Title,
+  Settings,
+  Controls,
+  HighScores,
+}
+
+impl MenuPage {
+  ///where going back from this page ends up, the page it was opened from
+  pub fn parent(self) -> Self {
+    match self {
+      MenuPage::Controls => MenuPage::Settings,
+      _ => MenuPage::Title,
+    }
+  }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MenuAction {
+  Start,
+  StartCoop,
+  Settings,
+  HighScores,
+  Quit,
+  Back,
+  PlayAgain,
+  Resume,
+  MainMenu,
+  ToggleFullscreen,
+  MasterVolume,
+  MusicVolume,
+  SfxVolume,
+  TouchControls,
+  TouchHand,
+  TouchSize,
+  TouchOpacity,
+  CoopScore,
+  EnterName,
+  WatchReplay,
+  Controls,
+  Rebind(ControlAction),
+  KeyboardLayout,
+  StickDeadzone,
+  StickResponse,
+  ResetControls,
+}
+
+#[derive(Event)]
+pub struct MenuActionEvent(pub MenuAction);
+
+///left / right pushed on the selected item, for settings that have a range
+#[derive(Event)]
+pub struct MenuAdjustEvent {
+  pub action: MenuAction,
+  pub step: i32,
+}
+
+#[derive(Component)]
+#[require(Button)]
+pub struct MenuItem {
+  index: usize,
+  pub action: MenuAction,
+}
+
+#[derive(Resource, Default)]
+struct MenuSelection {
+  index: usize,
+  last_direction: Vec2,
+}
+
+///full screen container menu screens are built in
+pub fn menu_root() -> impl Bundle {
+  (
+    Node {
+      width: Val::Percent(100.),
+      height: Val::Percent(100.),
+      flex_direction: FlexDirection::Column,
+      justify_content: JustifyContent::Center,
+      align_items: AlignItems::Center,
+      row_gap: Val::Px(12.),
+      ..default()
+    },
+    BackgroundColor(MENU_BACKGROUND_COLOUR),
+  )
+}
+
+pub fn menu_title(text: &str, font: Handle<Font>) -> impl Bundle {
+  (
+    Text::new(text),
+    TextFont {
+      font,
+      font_size: MENU_TITLE_FONT_SIZE,
+      ..default()
+    },
+    Node {
+      margin: UiRect::bottom(Val::Px(20.)),
+      ..default()
+    },
+  )
+}
+
+pub fn menu_heading(text: &str, font: Handle<Font>) -> impl Bundle {
+  (
+    Text::new(text),
+    TextFont {
+      font,
+      font_size: MENU_HEADING_FONT_SIZE,
+      ..default()
+    },
+    Node {
+      margin: UiRect::bottom(Val::Px(12.)),
+      ..default()
+    },
+  )
+}
+
+///selectable menu entry, items are navigated in index order
+pub fn menu_item(index: usize, text: &str, action: MenuAction, font: Handle<Font>) -> impl Bundle {
+  (
+    MenuItem { index, action },
+    Text::new(text),
+    TextFont {
+      font,
+      font_size: MENU_ITEM_FONT_SIZE,
+      ..default()
+    },
+    TextColor(MENU_ITEM_COLOUR),
+    Node {
+      padding: UiRect::axes(Val::Px(20.), Val::Px(4.)),
+      ..default()
+    },
+  )
+}
+
+fn spawn_title_screen(
+  mut commands: Commands,
+  scene_assets: Res<SceneAssets>,
+  replays: Res<Replays>,
+) {
+  let font = scene_assets.font.clone();
+  let mut items = vec![
+    ("Start", MenuAction::Start),
+    ("Co-op", MenuAction::StartCoop),
+    ("Settings", MenuAction::Settings),
+    ("High scores", MenuAction::HighScores),
+  ];
+  if replays.best.is_some() {
+    items.push(("Watch replay", MenuAction::WatchReplay));
+  }
+  //nothing to quit to in a browser tab
+  #[cfg(not(target_arch = "wasm32"))]
+  items.push(("Quit", MenuAction::Quit));
+
+  commands
+    .spawn((StateScoped(MenuPage::Title), menu_root()))
+    .with_children(|parent| {
+      parent.spawn(menu_title(APP_NAME, font.clone()));
+      for (index, (text, action)) in items.into_iter().enumerate() {
+        parent.spawn(menu_item(index, text, action, font.clone()));
+      }
+    });
+}
+
+fn menu_actions(
+  mut ev_menu_action_reader: EventReader<MenuActionEvent>,
+  mut ev_game_state_writer: EventWriter<GameStateEvent>,
+  mut ev_app_exit_writer: EventWriter<AppExit>,
+  page: Res<State<MenuPage>>,
+  mut next_page: ResMut<NextState<MenuPage>>,
+  mut players: ResMut<Players>,
+) {
+  for MenuActionEvent(action) in ev_menu_action_reader.read() {
+    match action {
+      MenuAction::Start => {
+        players.0 = 1;
+        ev_game_state_writer.write(GameStateEvent::new(GameState::Playing));
+      }
+      MenuAction::StartCoop => {
+        players.0 = MAX_PLAYERS;
+        ev_game_state_writer.write(GameStateEvent::new(GameState::Playing));
+      }
+      MenuAction::Settings => next_page.set(MenuPage::Settings),
+      MenuAction::HighScores => next_page.set(MenuPage::HighScores),
+      MenuAction::Controls => next_page.set(MenuPage::Controls),
+      MenuAction::Back => next_page.set(page.get().parent()),
+      MenuAction::Quit => {
+        info!("quitting");
+        ev_app_exit_writer.write(AppExit::Success);
+      }
+      _ => {}
+    }
+  }
+}
+
+//pause / back button steps back out of sub pages
+fn menu_back(
+  mut ev_trigger_reader: EventReader<InputTriggerEvent>,
+  page: Res<State<MenuPage>>,
+  mut next_page: ResMut<NextState<MenuPage>>,
+) {
+  for InputTriggerEvent {
+    action, input_type, ..
+  } in ev_trigger_reader.read()
+  {
+    if *action == InputEventAction::Pause
+      && *input_type == InputEventType::Pressed
+      && *page.get() != MenuPage::Title
+    {
+      next_page.set(page.get().parent());
+    }
+  }
+}
+
+//new menu on screen, start from the top
+fn reset_selection(query: Query<(), Added<MenuItem>>, mut selection: ResMut<MenuSelection>) {
+  if !query.is_empty() {
+    selection.index = 0;
+  }
+}
+
+fn input_navigation(
+  query: Query<&MenuItem>,
+  mut selection: ResMut<MenuSelection>,
+  mut ev_movement_reader: EventReader<InputMovementEvent>,
+  mut ev_trigger_reader: EventReader<InputTriggerEvent>,
+  mut ev_menu_action_writer: EventWriter<MenuActionEvent>,
+  mut ev_menu_adjust_writer: EventWriter<MenuAdjustEvent>,
+) {
+  let direction: Vec2 = ev_movement_reader.read().map(|ev| ev.direction).sum();
+  let select = ev_trigger_reader
+    .read()
+    .any(|ev| ev.action == InputEventAction::Shoot && ev.input_type == InputEventType::Pressed);
+
+  let count = query.iter().count();
+  if count == 0 {
+    return;
+  }
+
+  let Some(item) = query.iter().find(|item| item.index == selection.index) else {
+    selection.index = 0;
+    return;
+  };
+
+  //only step once per push of the stick / key
+  if selection.last_direction.y.abs() < MENU_NAVIGATE_THRESHOLD {
+    if direction.y > MENU_NAVIGATE_THRESHOLD {
+      selection.index = (selection.index + count - 1) % count;
+    } else if direction.y < -MENU_NAVIGATE_THRESHOLD {
+      selection.index = (selection.index + 1) % count;
+    }
+  }
+  //movement x is flipped to match the play field, so right is negative
+  if selection.last_direction.x.abs() < MENU_NAVIGATE_THRESHOLD
+    && direction.x.abs() > MENU_NAVIGATE_THRESHOLD
+  {
+    ev_menu_adjust_writer.write(MenuAdjustEvent {
+      action: item.action,
+      step: -direction.x.signum() as i32,
+    });
+  }
+  selection.last_direction = direction;
+
+  if select {
+    ev_menu_action_writer.write(MenuActionEvent(item.action));
+  }
+}
+
+//mouse and touch go through bevy ui interactions
+fn pointer_navigation(
+  query: Query<(&Interaction, &MenuItem), Changed<Interaction>>,
+  mut selection: ResMut<MenuSelection>,
+  mut ev_menu_action_writer: EventWriter<MenuActionEvent>,
+) {
+  for (interaction, item) in query.iter() {
+    match interaction {
+      Interaction::Pressed => {
+        selection.index = item.index;
+        ev_menu_action_writer.write(MenuActionEvent(item.action));
+      }
+      Interaction::Hovered => selection.index = item.index,
+      Interaction::None => {}
+    }
+  }
+}
+
+fn highlight_selection(
+  mut query: Query<(&MenuItem, &mut TextColor)>,
+  selection: Res<MenuSelection>,
+) {
+  for (item, mut colour) in query.iter_mut() {
+    colour.0 = if item.index == selection.index {
+      MENU_SELECTED_COLOUR
+    } else {
+      MENU_ITEM_COLOUR
+    };
+  }
+}
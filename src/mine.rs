@@ -1,10 +1,9 @@
 use bevy::{math::VectorSpace, prelude::*};
-use rand::Rng;
 
-use crate::{actions::{Drift, PlayerProximityTest, TrackToTarget}, asset_loader::SceneAssets, bounds_check::BoundsDespawn, collision_detection::Collider, effect_sprite::{EffectSpriteEvent, EffectSpriteType}, enemy::{Enemy, ENEMY_START_POINT_X, ENEMY_START_POINT_Z_BOUNDS_MAX}, game_manager::PointEvent, health::Health, hit_marker::HitMarker, hook::Hookable, movement::{Acceleration, Roller, Velocity}, scheduling::GameSchedule};
+use crate::{ai::AiConfigCollection, asset_loader::SceneAssets, bounds_check::BoundsDespawn, projectile::ProjectileKind, collision_detection::Collider, death::{Destroyable, Remains}, effect_sprite::EffectSpriteType, enemy::{Enemy, EnemyKind, SpawnEnemyEvent}, health::Health, hit_marker::HitMarker, hook::Hookable, movement::{Acceleration, Roller, Velocity}, scheduling::GameSchedule, weapon::{Weapon, WeaponProfile}};
 
 
-const MINE_SPAWN_TIME_SECONDS: f32 = 3.0;
+const MINE_SPEED: f32 = 10.;
 const MINE_COLLISION_RADIUS: f32 = 1.6;
 const MINE_COLLISION_DAMAGE: f32 = -30.;
 const MINE_HEALTH:f32 = 10.;
@@ -12,27 +11,27 @@ const MINE_SPIN_SPEED:f32 = 1.2;
 const MINE_HOOK_TRANSLATION: Vec3 = Vec3::new(-1., 0., 0.);
 const MINE_HOOK_ROTATION: f32 = 0.;
 const MINE_NAME: &str = "mine";
+const MINE_BLAST_SIZE: f32 = 3.0;
+//harmless in the wild, a captured one fires pairs of homing missiles
+const MINE_CAPTURED_WEAPON: WeaponProfile = WeaponProfile {
+  fire_rate: 0.8,
+  projectiles: 1,
+  spread: 0.,
+  burst: 2,
+  burst_interval: 0.2,
+  projectile_speed: 30.,
+  damage: -30.,
+  scale: 1.5,
+  muzzle_offset: Vec3::new(-1.5, 0., 0.),
+  projectile: ProjectileKind::Homing,
+};
 
 const MINE_POINTS:u64 = 20;
 pub struct MinePlugin;
 
 impl Plugin for MinePlugin{
   fn build(&self, app: &mut App) {
-    app.add_systems(Update, (spawn_mine ).in_set(GameSchedule::EntityUpdates))
-    .add_systems(Update, check_dead.in_set(GameSchedule::DespawnEntities));
-  }
-}
-
-#[derive(Deref, DerefMut)]
-pub struct SpawnTimer(Timer);
-
-impl Default for SpawnTimer {
-
-fn default() -> Self {
-    Self(Timer::from_seconds(
-      MINE_SPAWN_TIME_SECONDS,
-      TimerMode::Repeating,
-    ))
+    app.add_systems(FixedUpdate, (spawn_mine ).in_set(GameSchedule::EntityUpdates));
   }
 }
 
@@ -44,66 +43,35 @@ struct Mine;
 
 fn spawn_mine(
   mut commands:Commands,
-  mut spawn_timer:Local<SpawnTimer>,
-  time:Res<Time>,
+  mut ev_spawn_enemy_reader: EventReader<SpawnEnemyEvent>,
   scene_assets:Res<SceneAssets>,
+  ai_configs:Res<AiConfigCollection>,
 ){
-  spawn_timer.tick(time.delta());
-  if !spawn_timer.just_finished(){ 
-    return;
-  }
-
-  let mut rng = rand::thread_rng();
-  let spawn_pos = rng.gen_range(-1. ..1.);
-  let start_z = ENEMY_START_POINT_Z_BOUNDS_MAX * spawn_pos;
-  //ai_data_collection.get.get_behaviour_handle(MINE_NAME, "" )
+  for &SpawnEnemyEvent { kind, translation, velocity } in ev_spawn_enemy_reader.read() {
+    if kind != EnemyKind::Mine {
+      continue;
+    }
 
 commands.spawn((
     Mine,
     SceneRoot(scene_assets.mine.clone()),
-    Transform::from_translation(Vec3::new(ENEMY_START_POINT_X, 0., start_z)),
-    Velocity(Vec3::new(10., 0., 0.)),
-    Collider{
-      radius: MINE_COLLISION_RADIUS,
-      collision_damage: MINE_COLLISION_DAMAGE,
-    },
+    Transform::from_translation(translation),
+    Velocity(velocity.unwrap_or(Vec3::new(MINE_SPEED, 0., 0.))),
+    Collider::new(MINE_COLLISION_RADIUS, MINE_COLLISION_DAMAGE),
     Hookable::new(
       MINE_HOOK_TRANSLATION,
       Quat::from_rotation_z(MINE_HOOK_ROTATION),
     ),
     Health::new(MINE_HEALTH),
+    Weapon::new(None, Some(MINE_CAPTURED_WEAPON)),
+    Destroyable::new(
+      Remains::Effect(EffectSpriteType::Splosion),
+      MINE_BLAST_SIZE,
+      MINE_POINTS,
+    ),
     Roller::new(0.2, 0.1, MINE_SPIN_SPEED),
-    Drift::new(Vec3::new(0.5, 0., 1.), Vec3::new(0., 0., -0.5), 0.2),
     Acceleration::new(Vec3::ZERO, 0., 100.),
-    PlayerProximityTest::new(300.0,0.2, |commands, entity, target|->_{
-      commands.entity(entity)
-        .remove::<PlayerProximityTest>()
-        .insert(TrackToTarget::new(target, 50.0, 0.05));
-    }),
+    ai_configs.get_behaviour(MINE_NAME, ""),
   ));
-}
-
-fn check_dead(
-  mut commands: Commands,
-  query: Query<(Entity, &Health, &GlobalTransform, &Velocity), With<Mine>>,
-  mut ev_point_writer: EventWriter<PointEvent>,
-  mut ev_splosion_writer: EventWriter<EffectSpriteEvent>,
-) {
-  for (entity, health, transform, velocity) in query.iter() {
-    if health.value <= 0. {
-      info!("dead {:?}", entity);
-      ev_splosion_writer.write( 
-        EffectSpriteEvent::new(  
-        transform.translation() + Vec3::new(0., 0., 0.),
-        3.0,
-        velocity.0,
-        EffectSpriteType::Splosion, 
-      ));
-      
-
-      commands.entity(entity).despawn();
-      ev_point_writer.write(PointEvent(MINE_POINTS));
-    }
   }
 }
-
@@ -1,9 +1,10 @@
-use crate::scheduling::GameSchedule;
+use crate::{interpolation::Interpolated, scheduling::GameSchedule};
 use bevy::prelude::*;
 
 pub struct MovementPlugin;
 
 #[derive(Component, Default, Deref, DerefMut)]
+#[require(Interpolated)]
 pub struct Velocity(pub Vec3);
 
 const STOPPED_SPEED: f32 = 2.;
@@ -30,12 +31,12 @@ impl Plugin for MovementPlugin {
   fn build(&self, app: &mut App) {
     app
       .add_systems(
-        Update,
+        FixedUpdate,
         (update_velocity, update_position)
           .chain()
           .in_set(GameSchedule::EntityUpdates),
       )
-      .add_systems(Update, update_roll.in_set(GameSchedule::EntityUpdates));
+      .add_systems(FixedUpdate, update_roll.in_set(GameSchedule::EntityUpdates));
   }
 }
 
@@ -74,7 +75,7 @@ fn update_position(mut query: Query<(&mut Transform, &Velocity)>, time: Res<Time
   }
 }
 
-fn update_velocity(mut query: Query<(&mut Velocity, &Acceleration)>, time: Res<Time>) {
+pub fn update_velocity(mut query: Query<(&mut Velocity, &Acceleration)>, time: Res<Time>) {
   for (mut velocity, acceleration) in &mut query {
     let mut vel = velocity.0;
     let mut acc = acceleration.acceleration;
@@ -0,0 +1,112 @@
+use bevy::{
+  prelude::*,
+  window::{AppLifecycle, WindowFocused},
+};
+
+use crate::{
+  asset_loader::SceneAssets,
+  input::{InputEventAction, InputEventType, InputTriggerEvent},
+  menu::{menu_item, menu_root, menu_title, MenuAction, MenuActionEvent},
+  scheduling::GameSchedule,
+  state::{GameState, GameStateEvent, PauseState},
+};
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .add_systems(
+        OnEnter(PauseState::Paused),
+        (pause_time, spawn_pause_screen),
+      )
+      .add_systems(OnExit(PauseState::Paused), resume_time)
+      .add_systems(
+        Update,
+        (toggle_pause, pause_on_suspend)
+          .after(GameSchedule::ReadInput)
+          .run_if(in_state(GameState::Playing)),
+      )
+      .add_systems(Update, pause_actions.run_if(in_state(PauseState::Paused)));
+  }
+}
+
+//freezing virtual time stops every timer, effect and shader animation in one go
+fn pause_time(mut time: ResMut<Time<Virtual>>) {
+  info!("pausing");
+  time.pause();
+}
+
+fn resume_time(mut time: ResMut<Time<Virtual>>) {
+  info!("resuming");
+  time.unpause();
+}
+
+fn toggle_pause(
+  mut ev_trigger_reader: EventReader<InputTriggerEvent>,
+  pause_state: Res<State<PauseState>>,
+  mut next_pause_state: ResMut<NextState<PauseState>>,
+) {
+  for InputTriggerEvent {
+    action, input_type, ..
+  } in ev_trigger_reader.read()
+  {
+    if *action == InputEventAction::Pause && *input_type == InputEventType::Pressed {
+      next_pause_state.set(match pause_state.get() {
+        PauseState::Running => PauseState::Paused,
+        PauseState::Paused => PauseState::Running,
+      });
+    }
+  }
+}
+
+//don't keep playing in the background when we lose focus or get suspended
+fn pause_on_suspend(
+  mut ev_lifecycle_reader: EventReader<AppLifecycle>,
+  mut ev_focus_reader: EventReader<WindowFocused>,
+  mut next_pause_state: ResMut<NextState<PauseState>>,
+) {
+  let suspended = ev_lifecycle_reader
+    .read()
+    .any(|ev| matches!(ev, AppLifecycle::WillSuspend | AppLifecycle::Suspended));
+  let unfocused = ev_focus_reader.read().any(|ev| !ev.focused);
+  if suspended || unfocused {
+    next_pause_state.set(PauseState::Paused);
+  }
+}
+
+fn spawn_pause_screen(mut commands: Commands, scene_assets: Res<SceneAssets>) {
+  commands
+    .spawn((StateScoped(PauseState::Paused), menu_root()))
+    .with_children(|parent| {
+      parent.spawn(menu_title("Paused", scene_assets.font.clone()));
+      parent.spawn(menu_item(
+        0,
+        "Resume",
+        MenuAction::Resume,
+        scene_assets.font.clone(),
+      ));
+      parent.spawn(menu_item(
+        1,
+        "Main menu",
+        MenuAction::MainMenu,
+        scene_assets.font.clone(),
+      ));
+    });
+}
+
+fn pause_actions(
+  mut ev_menu_action_reader: EventReader<MenuActionEvent>,
+  mut next_pause_state: ResMut<NextState<PauseState>>,
+  mut ev_game_state_writer: EventWriter<GameStateEvent>,
+) {
+  for MenuActionEvent(action) in ev_menu_action_reader.read() {
+    match action {
+      MenuAction::Resume => next_pause_state.set(PauseState::Running),
+      MenuAction::MainMenu => {
+        ev_game_state_writer.write(GameStateEvent::new(GameState::Menu));
+      }
+      _ => {}
+    }
+  }
+}
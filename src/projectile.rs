@@ -0,0 +1,336 @@
+//! the different kinds of thing that get fired, how each looks, moves, lasts and
+//! goes off, looked up by `ProjectileKind` when a shot's spawned
+use bevy::{platform::collections::HashMap, prelude::*};
+
+use crate::{
+  bullet::Bullet,
+  effect_sprite::{EffectSpriteEvent, EffectSpriteType},
+  movement::{update_velocity, Velocity},
+  scheduling::GameSchedule,
+};
+
+//player shots are cool colours, enemy shots warm, whatever the kind
+const STRAIGHT_COLOURS: [Color; 2] = [Color::srgb(0.4, 1.6, 2.0), Color::srgb(2.0, 1.8, 0.2)];
+const HOMING_COLOURS: [Color; 2] = [Color::srgb(0.4, 2.0, 0.8), Color::srgb(2.0, 0.9, 0.2)];
+const ORB_COLOURS: [Color; 2] = [Color::srgb(1.0, 0.6, 2.0), Color::srgb(2.0, 0.3, 0.6)];
+const BEAM_COLOURS: [Color; 2] = [Color::srgb(0.6, 2.0, 2.0), Color::srgb(2.0, 0.4, 0.2)];
+const BOUNCING_COLOURS: [Color; 2] = [Color::srgb(0.4, 2.0, 1.4), Color::srgb(2.0, 1.4, 0.2)];
+const MINE_LAYER_COLOURS: [Color; 2] = [Color::srgb(0.6, 1.2, 2.0), Color::srgb(1.6, 0.2, 0.2)];
+
+const BULLET_SIZE: f32 = 0.5;
+const ORB_SIZE: f32 = 1.4;
+const BEAM_LENGTH: f32 = 6.0;
+const BEAM_WIDTH: f32 = 0.2;
+//beams go through most things in their way
+const BEAM_PIERCING: u32 = 8;
+//how hard homing shots turn
+const HOMING_ACCELERATION: f32 = 60.0;
+//bouncing shots come back off the top and bottom of the play area
+const BOUNCE_Z: f32 = 30.0;
+const BOUNCES: u32 = 3;
+//laid mines slow to a stop and sit there a while
+const MINE_LAYER_DAMPING: f32 = 12.0;
+
+pub struct ProjectilePlugin;
+
+impl Plugin for ProjectilePlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .init_resource::<ProjectileRegistry>()
+      .add_systems(Startup, register_projectiles)
+      .add_systems(
+        FixedUpdate,
+        (
+          (bounce, face_velocity.after(update_velocity)).in_set(GameSchedule::EntityUpdates),
+          expire.in_set(GameSchedule::PreDespawnEntities),
+        ),
+      );
+  }
+}
+
+///what gets fired
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum ProjectileKind {
+  ///a little ball that flies straight on
+  #[default]
+  Straight,
+  ///turns after the nearest thing on the other side
+  Homing,
+  ///big and slow
+  Orb,
+  ///a long bolt that goes through what it hits
+  Beam,
+  ///comes back off the top and bottom of the play area
+  Bouncing,
+  ///slows to a stop and waits for something to run into it
+  MineLayer,
+}
+
+///how a kind of projectile gets about once it's fired
+#[derive(Clone, Copy, Debug)]
+pub enum ProjectileMovement {
+  Straight,
+  Homing { acceleration: f32 },
+  Bouncing { bounces: u32 },
+  Drifting { damping: f32 },
+}
+
+///everything about one kind of projectile
+#[derive(Clone, Debug)]
+pub struct ProjectileDef {
+  pub mesh: Handle<Mesh>,
+  ///one for players' shots, one for enemies'
+  pub materials: [Handle<StandardMaterial>; 2],
+  pub movement: ProjectileMovement,
+  ///multiplies the speed a weapon fires it at, on top of however fast the shooter's going
+  pub speed: f32,
+  ///seconds before it's gone, none to keep going until it's off screen
+  pub lifetime: Option<f32>,
+  ///how many things it hits in all, see `ShootEvent::piercing`
+  pub piercing: Option<u32>,
+  pub hit_effect: EffectSpriteType,
+  pub hit_effect_size: f32,
+}
+
+impl ProjectileDef {
+  pub fn material(&self, player: bool) -> Handle<StandardMaterial> {
+    self.materials[if player { 0 } else { 1 }].clone()
+  }
+}
+
+#[derive(Resource, Default)]
+pub struct ProjectileRegistry {
+  kinds: HashMap<ProjectileKind, ProjectileDef>,
+}
+
+impl ProjectileRegistry {
+  pub fn register(&mut self, kind: ProjectileKind, def: ProjectileDef) {
+    self.kinds.insert(kind, def);
+  }
+
+  ///falls back on a plain shot for anything that hasn't been registered
+  pub fn get(&self, kind: ProjectileKind) -> Option<&ProjectileDef> {
+    self
+      .kinds
+      .get(&kind)
+      .or_else(|| self.kinds.get(&ProjectileKind::Straight))
+  }
+}
+
+///comes back off the edges of the play area this many more times
+#[derive(Component)]
+pub struct Bouncing {
+  pub bounces_remaining: u32,
+}
+
+///gone once the time runs out
+#[derive(Component)]
+pub struct Lifetime(pub Timer);
+
+fn register_projectiles(
+  mut registry: ResMut<ProjectileRegistry>,
+  mut meshes: ResMut<Assets<Mesh>>,
+  mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+  let mut def = |mesh: Mesh, colours: [Color; 2]| ProjectileDef {
+    mesh: meshes.add(mesh),
+    materials: colours.map(|colour| materials.add(colour)),
+    movement: ProjectileMovement::Straight,
+    speed: 1.,
+    lifetime: None,
+    piercing: None,
+    hit_effect: EffectSpriteType::Ricochet,
+    hit_effect_size: 1.,
+  };
+  let ball = |radius: f32, subdivisions: u32| {
+    Sphere::new(radius)
+      .mesh()
+      .kind(bevy::render::mesh::SphereKind::Ico { subdivisions })
+      .build()
+  };
+
+  let straight = def(ball(BULLET_SIZE, 3), STRAIGHT_COLOURS);
+  let homing = ProjectileDef {
+    movement: ProjectileMovement::Homing {
+      acceleration: HOMING_ACCELERATION,
+    },
+    lifetime: Some(4.),
+    hit_effect: EffectSpriteType::Splosion,
+    hit_effect_size: 1.5,
+    ..def(Cuboid::new(1.2, 0.4, 0.4).into(), HOMING_COLOURS)
+  };
+  let orb = ProjectileDef {
+    speed: 0.5,
+    hit_effect: EffectSpriteType::Splosion,
+    hit_effect_size: 2.5,
+    ..def(ball(ORB_SIZE, 2), ORB_COLOURS)
+  };
+  let beam = ProjectileDef {
+    lifetime: Some(1.5),
+    piercing: Some(BEAM_PIERCING),
+    hit_effect_size: 1.5,
+    ..def(
+      Cuboid::new(BEAM_LENGTH, BEAM_WIDTH, BEAM_WIDTH).into(),
+      BEAM_COLOURS,
+    )
+  };
+  let bouncing = ProjectileDef {
+    movement: ProjectileMovement::Bouncing { bounces: BOUNCES },
+    lifetime: Some(6.),
+    ..def(ball(BULLET_SIZE, 1), BOUNCING_COLOURS)
+  };
+  let mine_layer = ProjectileDef {
+    movement: ProjectileMovement::Drifting {
+      damping: MINE_LAYER_DAMPING,
+    },
+    lifetime: Some(8.),
+    hit_effect: EffectSpriteType::Splosion,
+    hit_effect_size: 3.,
+    ..def(ball(0.8, 0), MINE_LAYER_COLOURS)
+  };
+  for (kind, def) in [
+    (ProjectileKind::Straight, straight),
+    (ProjectileKind::Homing, homing),
+    (ProjectileKind::Orb, orb),
+    (ProjectileKind::Beam, beam),
+    (ProjectileKind::Bouncing, bouncing),
+    (ProjectileKind::MineLayer, mine_layer),
+  ] {
+    registry.register(kind, def);
+  }
+}
+
+fn bounce(
+  mut commands: Commands,
+  mut query: Query<(Entity, &mut Bouncing, &mut Velocity, &GlobalTransform)>,
+) {
+  for (entity, mut bouncing, mut velocity, transform) in query.iter_mut() {
+    let z = transform.translation().z;
+    //only on the way out, so it can't get stuck flipping back and forth
+    if z.abs() < BOUNCE_Z || z * velocity.z <= 0. {
+      continue;
+    }
+    velocity.z = -velocity.z;
+    bouncing.bounces_remaining = bouncing.bounces_remaining.saturating_sub(1);
+    if bouncing.bounces_remaining == 0 {
+      commands.entity(entity).remove::<Bouncing>();
+    }
+  }
+}
+
+//long shots point the way they're going as homing ones turn and bouncing ones flip
+fn face_velocity(mut query: Query<(&mut Transform, &Velocity), With<Bullet>>) {
+  for (mut transform, velocity) in query.iter_mut() {
+    //left as they were once they've stopped
+    if let Some(direction) = velocity.try_normalize() {
+      transform.rotation = Quat::from_rotation_arc(Vec3::X, direction);
+    }
+  }
+}
+
+//run out of time, going off where they are
+fn expire(
+  mut commands: Commands,
+  mut query: Query<(Entity, &mut Lifetime, &Bullet, &GlobalTransform)>,
+  registry: Res<ProjectileRegistry>,
+  time: Res<Time>,
+  mut ev_effect_sprite_writer: EventWriter<EffectSpriteEvent>,
+) {
+  for (entity, mut lifetime, bullet, transform) in query.iter_mut() {
+    lifetime.0.tick(time.delta());
+    if !lifetime.0.finished() {
+      continue;
+    }
+    if let Some(def) = registry.get(bullet.kind) {
+      ev_effect_sprite_writer.write(EffectSpriteEvent::new(
+        transform.translation(),
+        def.hit_effect_size,
+        Vec3::ZERO,
+        def.hit_effect,
+      ));
+    }
+    commands.entity(entity).despawn();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{bullet::ShootEvent, headless::HeadlessApp, health::DamageSource};
+
+  fn shoot(game: &mut HeadlessApp, kind: ProjectileKind, start: Vec3, velocity: Vec3) {
+    let shot = ShootEvent::new(DamageSource::ShipRam(0), start, velocity, -1., 1.);
+    game.send(ShootEvent { kind, ..shot });
+  }
+
+  #[test]
+  fn bouncing_shots_come_back_and_beams_run_out() {
+    let mut game = HeadlessApp::new();
+    shoot(
+      &mut game,
+      ProjectileKind::Bouncing,
+      Vec3::new(40., 0., 25.),
+      Vec3::new(0., 0., 40.),
+    );
+    game.step_secs(0.5);
+    let bullet = game.find::<Bouncing>().expect("still bouncing");
+    assert!(game.world().get::<Velocity>(bullet).unwrap().z < 0.);
+    assert_eq!(
+      game
+        .world()
+        .get::<Bouncing>(bullet)
+        .unwrap()
+        .bounces_remaining,
+      BOUNCES - 1
+    );
+    game.app.world_mut().despawn(bullet);
+
+    shoot(
+      &mut game,
+      ProjectileKind::Beam,
+      Vec3::new(40., 0., 0.),
+      Vec3::new(1., 0., 0.),
+    );
+    game.step_secs(1.);
+    assert!(game.find::<Bullet>().is_some());
+    game.step_secs(1.);
+    assert!(game.find::<Bullet>().is_none());
+  }
+
+  #[test]
+  fn laid_mines_stop_and_wait_until_they_run_out() {
+    let mut game = HeadlessApp::new();
+    shoot(
+      &mut game,
+      ProjectileKind::MineLayer,
+      Vec3::new(20., 0., 0.),
+      Vec3::new(-6., 0., 0.),
+    );
+    game.step_secs(1.);
+    let mine = game.find::<Bullet>().expect("still waiting");
+    assert!(game.world().get::<Velocity>(mine).unwrap().length() < 0.5);
+    let laid = game.world().get::<Transform>(mine).unwrap().translation;
+    assert!(laid.x > 17. && laid.x < 19.);
+    game.step_secs(8.);
+    assert!(game.find::<Bullet>().is_none());
+  }
+
+  #[test]
+  fn homing_missiles_point_where_they_are_going() {
+    let mut game = HeadlessApp::new();
+    game.spawn_target(Vec3::new(-10., 0., -30.), 1.6, 10.);
+    shoot(
+      &mut game,
+      ProjectileKind::Homing,
+      Vec3::ZERO,
+      Vec3::new(-20., 0., 0.),
+    );
+    game.step_secs(0.5);
+    let missile = game.find::<Bullet>().expect("still flying");
+    let velocity = game.world().get::<Velocity>(missile).unwrap().0;
+    let rotation = game.world().get::<Transform>(missile).unwrap().rotation;
+    //turned off towards the target, and turned to match. what's drawn is a step behind
+    assert!(velocity.z < -1.);
+    assert!((rotation * Vec3::X).angle_between(velocity) < 0.1);
+  }
+}
@@ -0,0 +1,394 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  asset_loader::SceneAssets,
+  game_manager::{Game, Players},
+  input::{
+    ControlInput, InputEventAction, InputEventType, InputMovementEvent, InputTriggerEvent,
+    PlayerControls,
+  },
+  menu::{MenuAction, MenuActionEvent, MenuPage},
+  rng::GameRng,
+  scheduling::GameSchedule,
+  state::{GameState, GameStateEvent, PauseState},
+  storage,
+};
+
+const LAST_REPLAY_KEY: &str = "last_replay";
+const BEST_REPLAY_KEY: &str = "best_replay";
+//idle time on the title screen before the best run starts playing itself
+const REPLAY_ATTRACT_SECONDS: f32 = 30.;
+const REPLAY_LABEL_FONT_SIZE: f32 = 20.;
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .init_resource::<Replays>()
+      .insert_resource(AttractTimer(Timer::from_seconds(
+        REPLAY_ATTRACT_SECONDS,
+        TimerMode::Once,
+      )))
+      .add_systems(Startup, load_replays)
+      .add_systems(OnEnter(GameState::Playing), start_recording)
+      .add_systems(
+        OnEnter(GameState::GameOver),
+        (finish_recording, end_playback.run_if(watching_replay)),
+      )
+      .add_systems(OnEnter(GameState::Menu), (stop_playback, discard_recording))
+      .add_systems(OnEnter(MenuPage::Title), reset_attract)
+      .add_systems(
+        FixedUpdate,
+        (
+          record_controls.run_if(recording),
+          play_controls.run_if(resource_exists::<Playback>),
+        )
+          .before(GameSchedule::UserInput)
+          .run_if(in_state(PauseState::Running)),
+      )
+      .add_systems(
+        Update,
+        (watch_replay, attract).run_if(in_state(MenuPage::Title)),
+      )
+      .add_systems(Update, skip_playback.after(GameSchedule::ReadInput));
+  }
+}
+
+///a game's seed and the controls for every step of it, enough to play it out again,
+///single player games only
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Replay {
+  pub seed: u64,
+  pub score: u64,
+  //run-length encoded, most steps are the same as the one before
+  steps: Vec<ReplaySteps>,
+}
+
+//how many steps in a row had these controls: count, movement x, movement y, buttons
+#[derive(Serialize, Deserialize, Clone, Copy)]
+struct ReplaySteps(u32, f32, f32, u8);
+
+//one bit per button pressed on the step
+const SHOOT_BIT: u8 = 1;
+const RECALL_BIT: u8 = 2;
+const JETTISON_BIT: u8 = 4;
+const CONSUME_BIT: u8 = 8;
+
+impl ReplaySteps {
+  fn new(controls: ControlInput) -> Self {
+    let bit = |pressed: bool, bit: u8| if pressed { bit } else { 0 };
+    Self(
+      1,
+      controls.movement.x,
+      controls.movement.y,
+      bit(controls.shoot, SHOOT_BIT)
+        | bit(controls.recall, RECALL_BIT)
+        | bit(controls.jettison, JETTISON_BIT)
+        | bit(controls.consume, CONSUME_BIT),
+    )
+  }
+
+  fn controls(&self) -> ControlInput {
+    ControlInput {
+      movement: Vec2::new(self.1, self.2),
+      shoot: self.3 & SHOOT_BIT != 0,
+      recall: self.3 & RECALL_BIT != 0,
+      jettison: self.3 & JETTISON_BIT != 0,
+      consume: self.3 & CONSUME_BIT != 0,
+    }
+  }
+}
+
+impl Replay {
+  fn push(&mut self, controls: ControlInput) {
+    if let Some(last) = self.steps.last_mut() {
+      if last.controls() == controls {
+        last.0 += 1;
+        return;
+      }
+    }
+    self.steps.push(ReplaySteps::new(controls));
+  }
+
+  fn save(&self, key: &str) {
+    match serde_json::to_string(self) {
+      Ok(json) => storage::save(key, &json),
+      Err(err) => warn!("failed writing replay: {:?}", err),
+    }
+  }
+}
+
+///the best run so far, played back from the title screen
+#[derive(Resource, Default)]
+pub struct Replays {
+  pub best: Option<Replay>,
+}
+
+//the game being played, step by step
+#[derive(Resource, Default)]
+struct Recording(Replay);
+
+///a replay being watched and how far through it we are
+#[derive(Resource)]
+pub struct Playback {
+  replay: Replay,
+  index: usize,
+  repeat: u32,
+  //how many were playing before, put back once it's over
+  players: usize,
+}
+
+impl Playback {
+  fn next(&mut self) -> Option<ControlInput> {
+    let steps = self.replay.steps.get(self.index)?;
+    let controls = steps.controls();
+    self.repeat += 1;
+    if self.repeat >= steps.0 {
+      self.index += 1;
+      self.repeat = 0;
+    }
+    Some(controls)
+  }
+}
+
+#[derive(Resource)]
+struct AttractTimer(Timer);
+
+#[derive(Component)]
+struct ReplayLabel;
+
+///is the game on screen a replay rather than someone playing
+pub fn watching_replay(playback: Option<Res<Playback>>) -> bool {
+  playback.is_some()
+}
+
+//someone playing on their own, with a recording going
+fn recording(
+  recording: Option<Res<Recording>>,
+  playback: Option<Res<Playback>>,
+  players: Res<Players>,
+) -> bool {
+  recording.is_some() && playback.is_none() && players.0 == 1
+}
+
+fn load_replays(mut replays: ResMut<Replays>) {
+  let Some(json) = storage::load(BEST_REPLAY_KEY) else {
+    return;
+  };
+  match serde_json::from_str(&json) {
+    Ok(best) => replays.best = Some(best),
+    Err(err) => warn!("failed reading replay: {:?}", err),
+  }
+}
+
+fn start_recording(
+  mut commands: Commands,
+  playback: Option<Res<Playback>>,
+  players: Res<Players>,
+  scene_assets: Res<SceneAssets>,
+) {
+  //whatever was left over from a game that never finished
+  commands.remove_resource::<Recording>();
+  if playback.is_none() {
+    if players.0 == 1 {
+      commands.insert_resource(Recording::default());
+    }
+    return;
+  }
+  commands.spawn((
+    StateScoped(GameState::Playing),
+    ReplayLabel,
+    Text::new("Replay - fire to exit"),
+    TextFont {
+      font: scene_assets.font.clone(),
+      font_size: REPLAY_LABEL_FONT_SIZE,
+      ..default()
+    },
+    Node {
+      position_type: PositionType::Absolute,
+      bottom: Val::Px(12.0),
+      left: Val::Px(12.0),
+      ..default()
+    },
+  ));
+}
+
+fn record_controls(mut recording: ResMut<Recording>, controls: Res<PlayerControls>) {
+  recording.0.push(controls.get(0));
+}
+
+fn finish_recording(
+  mut commands: Commands,
+  recording: Option<Res<Recording>>,
+  game: Single<&Game>,
+  rng: Res<GameRng>,
+  mut replays: ResMut<Replays>,
+) {
+  let Some(recording) = recording else {
+    return;
+  };
+  commands.remove_resource::<Recording>();
+  let replay = Replay {
+    seed: rng.seed(),
+    score: game.score,
+    ..recording.0.clone()
+  };
+  replay.save(LAST_REPLAY_KEY);
+  if replays
+    .best
+    .as_ref()
+    .is_none_or(|best| replay.score > best.score)
+  {
+    replay.save(BEST_REPLAY_KEY);
+    replays.best = Some(replay);
+  }
+}
+
+fn play_controls(
+  mut playback: ResMut<Playback>,
+  mut controls: ResMut<PlayerControls>,
+  mut ev_game_state_writer: EventWriter<GameStateEvent>,
+) {
+  match playback.next() {
+    Some(next) => controls.0[0] = next,
+    None => {
+      controls.0[0] = ControlInput::default();
+      ev_game_state_writer.write(GameStateEvent::new(GameState::Menu));
+    }
+  }
+}
+
+//fire bails out of a running replay, reads all the time so presses from the menu don't linger
+fn skip_playback(
+  mut ev_trigger_reader: EventReader<InputTriggerEvent>,
+  playback: Option<Res<Playback>>,
+  pause_state: Option<Res<State<PauseState>>>,
+  mut ev_game_state_writer: EventWriter<GameStateEvent>,
+) {
+  let fired = ev_trigger_reader
+    .read()
+    .any(|ev| ev.action == InputEventAction::Shoot && ev.input_type == InputEventType::Pressed);
+  let running = pause_state.is_some_and(|state| *state.get() == PauseState::Running);
+  if fired && running && playback.is_some() {
+    ev_game_state_writer.write(GameStateEvent::new(GameState::Menu));
+  }
+}
+
+fn end_playback(mut ev_game_state_writer: EventWriter<GameStateEvent>) {
+  ev_game_state_writer.write(GameStateEvent::new(GameState::Menu));
+}
+
+//back to however many were playing before the replay
+fn stop_playback(
+  mut commands: Commands,
+  playback: Option<Res<Playback>>,
+  mut players: ResMut<Players>,
+) {
+  let Some(playback) = playback else {
+    return;
+  };
+  players.0 = playback.players;
+  commands.remove_resource::<Playback>();
+}
+
+//quit part way through, nothing worth keeping
+fn discard_recording(mut commands: Commands) {
+  commands.remove_resource::<Recording>();
+}
+
+fn watch_replay(
+  mut commands: Commands,
+  mut ev_menu_action_reader: EventReader<MenuActionEvent>,
+  replays: Res<Replays>,
+  mut rng: ResMut<GameRng>,
+  mut players: ResMut<Players>,
+  mut ev_game_state_writer: EventWriter<GameStateEvent>,
+) {
+  for MenuActionEvent(action) in ev_menu_action_reader.read() {
+    if *action != MenuAction::WatchReplay {
+      continue;
+    }
+    let Some(replay) = &replays.best else {
+      continue;
+    };
+    info!("playing replay, score {}", replay.score);
+    rng.next_seed = Some(replay.seed);
+    commands.insert_resource(Playback {
+      replay: replay.clone(),
+      index: 0,
+      repeat: 0,
+      players: players.0,
+    });
+    players.0 = 1;
+    ev_game_state_writer.write(GameStateEvent::new(GameState::Playing));
+  }
+}
+
+fn reset_attract(mut attract: ResMut<AttractTimer>) {
+  attract.0.reset();
+}
+
+//nobody's touched anything for a while, show off the best run
+fn attract(
+  mut ev_movement_reader: EventReader<InputMovementEvent>,
+  mut ev_trigger_reader: EventReader<InputTriggerEvent>,
+  mut attract: ResMut<AttractTimer>,
+  time: Res<Time>,
+  replays: Res<Replays>,
+  mut ev_menu_action_writer: EventWriter<MenuActionEvent>,
+) {
+  let moved = ev_movement_reader.read().count() > 0;
+  let triggered = ev_trigger_reader.read().count() > 0;
+  if moved || triggered {
+    attract.0.reset();
+    return;
+  }
+  attract.0.tick(time.delta());
+  if attract.0.just_finished() && replays.best.is_some() {
+    ev_menu_action_writer.write(MenuActionEvent(MenuAction::WatchReplay));
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn controls(x: f32, shoot: bool) -> ControlInput {
+    ControlInput {
+      movement: Vec2::new(x, 0.),
+      shoot,
+      ..default()
+    }
+  }
+
+  #[test]
+  fn steps_play_back_as_recorded() {
+    let recorded = [
+      controls(0., false),
+      controls(0., false),
+      controls(1., true),
+      ControlInput {
+        jettison: true,
+        ..controls(1., false)
+      },
+      controls(1., false),
+      controls(1., false),
+    ];
+    let mut replay = Replay::default();
+    for step in recorded {
+      replay.push(step);
+    }
+    assert_eq!(replay.steps.len(), 4);
+
+    let mut playback = Playback {
+      replay,
+      index: 0,
+      repeat: 0,
+      players: 1,
+    };
+    let played: Vec<_> = std::iter::from_fn(|| playback.next()).collect();
+    assert!(played == recorded);
+  }
+}
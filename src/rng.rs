@@ -0,0 +1,77 @@
+use bevy::prelude::*;
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::state::GameState;
+
+pub struct RngPlugin;
+
+impl Plugin for RngPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .init_resource::<GameRng>()
+      .add_systems(OnEnter(GameState::Playing), seed_game);
+  }
+}
+
+///every random choice the simulation makes comes from here,
+///so a game plays out the same from the same seed and inputs
+#[derive(Resource, Deref, DerefMut)]
+pub struct GameRng {
+  #[deref]
+  rng: StdRng,
+  seed: u64,
+  ///used for the next game instead of a fresh seed
+  pub next_seed: Option<u64>,
+}
+
+impl GameRng {
+  ///what the current game was seeded with
+  pub fn seed(&self) -> u64 {
+    self.seed
+  }
+}
+
+impl Default for GameRng {
+  fn default() -> Self {
+    Self {
+      rng: StdRng::seed_from_u64(0),
+      seed: 0,
+      next_seed: None,
+    }
+  }
+}
+
+fn seed_game(mut rng: ResMut<GameRng>) {
+  let seed = rng.next_seed.take().unwrap_or_else(rand::random);
+  info!("game seed: {}", seed);
+  rng.rng = StdRng::seed_from_u64(seed);
+  rng.seed = seed;
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    enemy::{Enemy, EnemyKind, SpawnEnemyEvent},
+    headless::HeadlessApp,
+  };
+  use bevy::prelude::*;
+
+  //a drifting mine left to wander for a few seconds
+  fn mine_drift(seed: u64) -> Vec3 {
+    let mut game = HeadlessApp::with_seed(seed);
+    game.send(SpawnEnemyEvent::new(
+      EnemyKind::Mine,
+      Vec3::new(-40., 0., 0.),
+      Some(Vec3::ZERO),
+    ));
+    game.step_secs(3.);
+    let mine = game.find::<Enemy>().expect("mine still about");
+    game.world().get::<Transform>(mine).unwrap().translation
+  }
+
+  #[test]
+  fn same_seed_plays_out_the_same() {
+    assert_eq!(mine_drift(7), mine_drift(7));
+    assert_ne!(mine_drift(7), mine_drift(8));
+  }
+}
@@ -1,9 +1,17 @@
-use bevy::prelude::*;
+use bevy::{
+  ecs::schedule::ExecutorKind,
+  prelude::*,
+  transform::systems::{mark_dirty_trees, propagate_parent_transforms, sync_simple_transforms},
+};
 
-use crate::state::GameState;
+use crate::state::PauseState;
+
+///simulation steps per second, the same on every platform whatever the frame rate
+pub const GAME_STEP_HZ: f64 = 60.;
 
 #[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
 pub enum GameSchedule {
+  ReadInput,
   UserInput,
   EntityUpdates,
   CollisionDetection,
@@ -17,26 +25,50 @@ pub struct SchedulingPlugin;
 impl Plugin for SchedulingPlugin {
   fn build(&self, app: &mut App) {
     app
+      .insert_resource(Time::<Fixed>::from_hz(GAME_STEP_HZ))
       .configure_sets(
-        Update,
+        FixedUpdate,
         (
           GameSchedule::HealthAdjust,
           GameSchedule::PreDespawnEntities,
           GameSchedule::DespawnEntities,
           GameSchedule::UserInput,
           GameSchedule::EntityUpdates,
+          TransformSystem::TransformPropagate,
+          GameSchedule::CollisionDetection,
         )
           .chain()
-          .run_if(in_state(GameState::Playing)),
+          .run_if(in_state(PauseState::Running)),
       )
-      .configure_sets(
-        PostUpdate,
-        GameSchedule::CollisionDetection
-          .after(TransformSystem::TransformPropagate)
-          .run_if(in_state(GameState::Playing)),
+      //global transforms as the simulation left them, rendering has moved things since
+      .add_systems(
+        FixedFirst,
+        (
+          mark_dirty_trees,
+          propagate_parent_transforms,
+          sync_simple_transforms,
+        )
+          .chain()
+          .in_set(TransformSystem::TransformPropagate),
+      )
+      //and again after everything's moved, ready for collisions
+      .add_systems(
+        FixedUpdate,
+        (
+          mark_dirty_trees,
+          propagate_parent_transforms,
+          sync_simple_transforms,
+        )
+          .chain()
+          .in_set(TransformSystem::TransformPropagate),
       );
 
-      /*
+    //one system at a time in a fixed order, so the same inputs always play out the same way
+    app.edit_schedule(FixedUpdate, |schedule| {
+      schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+    });
+
+    /*
     app.edit_schedule(Update, |schedule| {
       schedule.set_build_settings(ScheduleBuildSettings {
         ambiguity_detection: LogLevel::Warn,
@@ -0,0 +1,241 @@
+use bevy::{
+  prelude::*,
+  window::{PrimaryWindow, WindowMode},
+};
+
+use crate::{
+  asset_loader::SceneAssets,
+  menu::{
+    menu_heading, menu_item, menu_root, MenuAction, MenuActionEvent, MenuAdjustEvent, MenuItem,
+    MenuPage,
+  },
+};
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .init_resource::<Settings>()
+      .add_systems(OnEnter(MenuPage::Settings), spawn_settings_page)
+      .add_systems(
+        Update,
+        (settings_actions, update_setting_labels)
+          .chain()
+          .run_if(in_state(MenuPage::Settings)),
+      )
+      //not when it's first put in, that'd knock the window out of whatever mode it started in
+      .add_systems(
+        Update,
+        apply_settings.run_if(resource_changed::<Settings>.and(not(resource_added::<Settings>))),
+      );
+  }
+}
+
+const VOLUME_STEPS: u32 = 10;
+///on screen touch control size and opacity go from 1 to this
+pub const TOUCH_STEPS: u32 = 5;
+
+#[derive(Resource)]
+pub struct Settings {
+  pub fullscreen: bool,
+  ///volumes in steps from 0 to VOLUME_STEPS
+  pub master_volume: u32,
+  pub music_volume: u32,
+  pub sfx_volume: u32,
+  ///thumbstick and buttons drawn over the game for touch screens
+  pub touch_controls: bool,
+  ///stick on the right and buttons on the left
+  pub left_handed: bool,
+  ///touch control size and opacity in steps from 1 to TOUCH_STEPS
+  pub touch_size: u32,
+  pub touch_opacity: u32,
+  ///co-op players score together rather than against each other
+  pub shared_score: bool,
+}
+
+impl Default for Settings {
+  fn default() -> Self {
+    Self {
+      fullscreen: false,
+      master_volume: VOLUME_STEPS,
+      music_volume: 7,
+      sfx_volume: VOLUME_STEPS,
+      touch_controls: cfg!(target_os = "android"),
+      left_handed: false,
+      touch_size: 3,
+      touch_opacity: 3,
+      shared_score: true,
+    }
+  }
+}
+
+impl Settings {
+  pub fn music_gain(&self) -> f32 {
+    gain(self.master_volume) * gain(self.music_volume)
+  }
+
+  pub fn sfx_gain(&self) -> f32 {
+    gain(self.master_volume) * gain(self.sfx_volume)
+  }
+
+  fn toggle_mut(&mut self, action: MenuAction) -> Option<&mut bool> {
+    match action {
+      MenuAction::ToggleFullscreen => Some(&mut self.fullscreen),
+      MenuAction::TouchControls => Some(&mut self.touch_controls),
+      MenuAction::TouchHand => Some(&mut self.left_handed),
+      MenuAction::CoopScore => Some(&mut self.shared_score),
+      _ => None,
+    }
+  }
+
+  fn touch_step_mut(&mut self, action: MenuAction) -> Option<&mut u32> {
+    match action {
+      MenuAction::TouchSize => Some(&mut self.touch_size),
+      MenuAction::TouchOpacity => Some(&mut self.touch_opacity),
+      _ => None,
+    }
+  }
+
+  fn volume_mut(&mut self, action: MenuAction) -> Option<&mut u32> {
+    match action {
+      MenuAction::MasterVolume => Some(&mut self.master_volume),
+      MenuAction::MusicVolume => Some(&mut self.music_volume),
+      MenuAction::SfxVolume => Some(&mut self.sfx_volume),
+      _ => None,
+    }
+  }
+}
+
+fn gain(volume: u32) -> f32 {
+  volume as f32 / VOLUME_STEPS as f32
+}
+
+fn on_off(value: bool) -> &'static str {
+  if value {
+    "On"
+  } else {
+    "Off"
+  }
+}
+
+//current text for settings entries, None for plain menu entries
+fn setting_label(action: MenuAction, settings: &Settings) -> Option<String> {
+  match action {
+    MenuAction::ToggleFullscreen => Some(format!("Fullscreen: {}", on_off(settings.fullscreen))),
+    MenuAction::MasterVolume => Some(format!("Volume: {}", settings.master_volume)),
+    MenuAction::MusicVolume => Some(format!("Music: {}", settings.music_volume)),
+    MenuAction::SfxVolume => Some(format!("Effects: {}", settings.sfx_volume)),
+    MenuAction::TouchControls => Some(format!(
+      "Touch controls: {}",
+      on_off(settings.touch_controls)
+    )),
+    MenuAction::TouchHand => Some(format!(
+      "Touch stick: {}",
+      if settings.left_handed {
+        "Right"
+      } else {
+        "Left"
+      }
+    )),
+    MenuAction::TouchSize => Some(format!("Touch size: {}", settings.touch_size)),
+    MenuAction::TouchOpacity => Some(format!("Touch opacity: {}", settings.touch_opacity)),
+    MenuAction::CoopScore => Some(format!(
+      "Co-op score: {}",
+      if settings.shared_score {
+        "Shared"
+      } else {
+        "Separate"
+      }
+    )),
+    _ => None,
+  }
+}
+
+fn spawn_settings_page(
+  mut commands: Commands,
+  scene_assets: Res<SceneAssets>,
+  settings: Res<Settings>,
+) {
+  let font = scene_assets.font.clone();
+  let entries = [
+    MenuAction::ToggleFullscreen,
+    MenuAction::MasterVolume,
+    MenuAction::MusicVolume,
+    MenuAction::SfxVolume,
+    MenuAction::TouchControls,
+    MenuAction::TouchHand,
+    MenuAction::TouchSize,
+    MenuAction::TouchOpacity,
+    MenuAction::CoopScore,
+    MenuAction::Controls,
+    MenuAction::Back,
+  ];
+  commands
+    .spawn((StateScoped(MenuPage::Settings), menu_root()))
+    .with_children(|parent| {
+      parent.spawn(menu_heading("Settings", font.clone()));
+      for (index, action) in entries.into_iter().enumerate() {
+        let label = setting_label(action, &settings).unwrap_or_else(|| {
+          match action {
+            MenuAction::Controls => "Controls",
+            _ => "Back",
+          }
+          .into()
+        });
+        parent.spawn(menu_item(index, &label, action, font.clone()));
+      }
+    });
+}
+
+fn settings_actions(
+  mut ev_menu_action_reader: EventReader<MenuActionEvent>,
+  mut ev_menu_adjust_reader: EventReader<MenuAdjustEvent>,
+  mut settings: ResMut<Settings>,
+) {
+  for MenuActionEvent(action) in ev_menu_action_reader.read() {
+    if let Some(toggle) = settings.toggle_mut(*action) {
+      *toggle = !*toggle;
+    }
+    //selecting a volume steps it up, wrapping round to silent
+    if let Some(volume) = settings.volume_mut(*action) {
+      *volume = (*volume + 1) % (VOLUME_STEPS + 1);
+    }
+    if let Some(touch_step) = settings.touch_step_mut(*action) {
+      *touch_step = *touch_step % TOUCH_STEPS + 1;
+    }
+  }
+  //right switches on / turns up, left switches off / turns down
+  for MenuAdjustEvent { action, step } in ev_menu_adjust_reader.read() {
+    if let Some(toggle) = settings.toggle_mut(*action) {
+      *toggle = *step > 0;
+    }
+    if let Some(volume) = settings.volume_mut(*action) {
+      *volume = volume.saturating_add_signed(*step).min(VOLUME_STEPS);
+    }
+    if let Some(touch_step) = settings.touch_step_mut(*action) {
+      *touch_step = touch_step
+        .saturating_add_signed(*step)
+        .clamp(1, TOUCH_STEPS);
+    }
+  }
+}
+
+fn update_setting_labels(mut query: Query<(&MenuItem, &mut Text)>, settings: Res<Settings>) {
+  if !settings.is_changed() {
+    return;
+  }
+  for (item, mut text) in query.iter_mut() {
+    if let Some(label) = setting_label(item.action, &settings) {
+      text.0 = label;
+    }
+  }
+}
+
+fn apply_settings(settings: Res<Settings>, mut window: Single<&mut Window, With<PrimaryWindow>>) {
+  window.mode = if settings.fullscreen {
+    WindowMode::BorderlessFullscreen(MonitorSelection::Current)
+  } else {
+    WindowMode::Windowed
+  };
+}
@@ -3,26 +3,39 @@ use std::f32::consts::PI;
 
 use crate::{
   asset_loader::SceneAssets,
-  collision_detection::{Collider, Player},
-  game_manager::PlayState,
-  health::Health,
+  audio::{Sfx, SfxEvent},
+  bounds_check::BoundsDespawn,
+  collider_shape::ColliderShape,
+  collision_detection::{Collider, CollisionLayer},
+  death::{DeathEvent, Destroyable, Remains},
+  game_manager::{Players, SpawnShipEvent},
+  health::{DamageSource, Health, HealthEvent},
   hit_marker::HitMarker,
-  hook::{hook_builder, Hook, HookReturnedEvent, Hookable},
-  input::{InputEventAction, InputEventType, InputMovementEvent, InputTriggerEvent},
+  hook::{hook_builder, Heavy, Hook, HookReturnedEvent, Hookable, Hooked},
+  input::PlayerControls,
   movement::{Acceleration, Velocity},
   scheduling::GameSchedule,
-  wreck::{Wreck, WreckedEvent},
 };
 
 const STARTING_TRANSLATION: Vec3 = Vec3::new(40.0, 0.0, 0.0);
+//gap along z between ships starting side by side in co-op
+const SHIP_START_SPACING: f32 = 16.0;
 const SHIP_ACCELERATION: f32 = 500.0;
 const SHIP_DAMPING: f32 = 150.0;
 const SHIP_MAX_SPEED: f32 = 40.0;
 const SHIP_MAX_PITCH: f32 = 0.1 * PI;
 const SHIP_PITCH_RATE: f32 = 2.;
-const SHIP_COLLISION_RADIUS: f32 = 1.8;
+//tail and nose of the capsule along the hull
+const SHIP_COLLISION_START: Vec3 = Vec3::new(-2.2, 0.0, 0.0);
+const SHIP_COLLISION_END: Vec3 = Vec3::new(1.2, 0.0, 0.0);
+const SHIP_COLLISION_RADIUS: f32 = 1.3;
 const SHIP_COLLISION_DAMAGE: f32 = -1000.0;
 const SHIP_INITIAL_HEALTH: f32 = 100.0;
+const SHIP_WRECK_ROLL_SPEED: f32 = 1.2;
+const SHIP_WRECK_TIME: f32 = 3.0;
+const SHIP_BLAST_SIZE: f32 = 3.0;
+const CAPTIVE_JETTISON_SPEED: f32 = 50.0;
+const CAPTIVE_CONSUME_HEALTH: f32 = 25.0;
 
 const SHIP_INVINCIBLE_TIME: f32 = 1.5;
 const SHIP_INVINCIBLE_FLICKER_RATE: f32 = 30.0;
@@ -38,41 +51,84 @@ pub struct ShipPlugin;
 impl Plugin for ShipPlugin {
   fn build(&self, app: &mut App) {
     app
-      .add_systems(OnEnter(PlayState::Alive), spawn_ship)
+      .add_systems(FixedUpdate, spawn_ship.in_set(GameSchedule::EntityUpdates))
       .add_systems(
-        Update,
-        (movement_controls, update_pitch, fire_controls)
+        FixedUpdate,
+        (
+          movement_controls,
+          update_pitch,
+          fire_controls,
+          captive_controls,
+        )
           .chain()
           .in_set(GameSchedule::UserInput),
       )
       .add_systems(
-        Update,
-        (bounds_check, retrieve_hook, check_dead, invincible).in_set(GameSchedule::EntityUpdates),
+        FixedUpdate,
+        (bounds_check, retrieve_hook, invincible).in_set(GameSchedule::EntityUpdates),
       )
       .add_systems(
-        Update,
+        FixedUpdate,
         remove_dead_captive.in_set(GameSchedule::PreDespawnEntities),
-      );
+      )
+      .add_observer(ship_destroyed);
   }
 }
 
-fn spawn_ship(mut commands: Commands, scene_assets: Res<SceneAssets>) {
+//players side by side down the right of the screen
+fn starting_translation(player: usize, players: usize) -> Vec3 {
+  let offset = player as f32 - (players.max(1) - 1) as f32 * 0.5;
+  STARTING_TRANSLATION + Vec3::new(0., 0., offset * SHIP_START_SPACING)
+}
+
+fn spawn_ship(
+  mut commands: Commands,
+  mut ev_spawn_ship_reader: EventReader<SpawnShipEvent>,
+  players: Res<Players>,
+  scene_assets: Res<SceneAssets>,
+) {
+  for &SpawnShipEvent { player } in ev_spawn_ship_reader.read() {
+    spawn_player_ship(&mut commands, &scene_assets, player, players.0);
+  }
+}
+
+fn spawn_player_ship(
+  commands: &mut Commands,
+  scene_assets: &SceneAssets,
+  player: usize,
+  players: usize,
+) {
   commands
     .spawn((
       PlayerShip { ..default() },
+      PlayerNumber(player),
       SceneRoot(scene_assets.ship.clone()),
-      Transform::from_translation(STARTING_TRANSLATION),
+      Transform::from_translation(starting_translation(player, players)),
       Acceleration {
         acceleration: Vec3::ZERO,
         damping: SHIP_DAMPING,
         max_speed: SHIP_MAX_SPEED,
       },
       Health::new(SHIP_INITIAL_HEALTH),
-      Collider {
-        radius: SHIP_COLLISION_RADIUS,
-        collision_damage: SHIP_COLLISION_DAMAGE,
-      },
-      Player,
+      Destroyable::new(
+        Remains::Wreck {
+          scene: scene_assets.ship.clone(),
+          roll_speed: SHIP_WRECK_ROLL_SPEED,
+          time_to_live: SHIP_WRECK_TIME,
+        },
+        SHIP_BLAST_SIZE,
+        0,
+      ),
+      Collider::shaped(
+        ColliderShape::Capsule {
+          start: SHIP_COLLISION_START,
+          end: SHIP_COLLISION_END,
+          radius: SHIP_COLLISION_RADIUS,
+        },
+        SHIP_COLLISION_DAMAGE,
+      ),
+      //out of the fight until the invincibility wears off
+      CollisionLayer::Neutral,
       Invincible {
         time: Timer::from_seconds(SHIP_INVINCIBLE_TIME, TimerMode::Once),
       },
@@ -84,8 +140,19 @@ fn spawn_ship(mut commands: Commands, scene_assets: Res<SceneAssets>) {
     ));
 }
 
+///which player something belongs to, 0 for player one,
+///on their ship, its hook and whatever it's captured
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct PlayerNumber(pub usize);
+
 #[derive(Component, Default)]
-#[require(Transform, Acceleration, Player, HitMarker)]
+#[require(
+  Transform,
+  Acceleration,
+  CollisionLayer = CollisionLayer::Player,
+  PlayerNumber,
+  HitMarker
+)]
 pub struct PlayerShip {
   target_pitch: f32,
   pitch: f32,
@@ -102,6 +169,11 @@ pub struct Captured {
   pub captor: Entity,
 }
 
+///a captive thrown at the enemies, rams whatever it hits
+#[derive(Component)]
+pub struct Jettisoned;
+
+///just spawned, kept on the neutral layer until the time runs out
 #[derive(Component)]
 pub struct Invincible {
   time: Timer,
@@ -112,153 +184,181 @@ fn invincible(
   mut query: Query<(&mut Invincible, &mut Visibility, Entity)>,
   time: Res<Time>,
 ) {
-  let Ok((mut invincible, mut visibilty, entity)) = query.single_mut() else {
-    return;
-  };
-  invincible.time.tick(time.delta());
-  if invincible.time.just_finished() {
-    commands.entity(entity).remove::<Invincible>();
-    *visibilty = Visibility::Visible;
-  } else {
-    *visibilty = match (invincible.time.elapsed_secs() * SHIP_INVINCIBLE_FLICKER_RATE
-      % SHIP_INVINCIBLE_FLICKER_RATIO)
-      .floor()
-    {
-      0.0 => Visibility::Hidden,
-      _ => Visibility::Visible,
+  for (mut invincible, mut visibilty, entity) in query.iter_mut() {
+    invincible.time.tick(time.delta());
+    if invincible.time.just_finished() {
+      commands
+        .entity(entity)
+        .remove::<Invincible>()
+        .insert(CollisionLayer::Player);
+      *visibilty = Visibility::Visible;
+    } else {
+      *visibilty = match (invincible.time.elapsed_secs() * SHIP_INVINCIBLE_FLICKER_RATE
+        % SHIP_INVINCIBLE_FLICKER_RATIO)
+        .floor()
+      {
+        0.0 => Visibility::Hidden,
+        _ => Visibility::Visible,
+      }
     }
   }
 }
 
 fn update_pitch(mut query: Query<(&mut PlayerShip, &mut Transform)>, time: Res<Time>) {
-  let Ok((mut ship, mut transform)) = query.single_mut() else {
-    return;
-  };
-  let diff = ship.target_pitch - ship.pitch;
-  let max_turn = SHIP_PITCH_RATE * time.delta_secs();
-  if max_turn > diff.abs() {
-    ship.pitch = ship.target_pitch;
-  } else {
-    ship.pitch += diff.signum() * max_turn;
+  for (mut ship, mut transform) in query.iter_mut() {
+    let diff = ship.target_pitch - ship.pitch;
+    let max_turn = SHIP_PITCH_RATE * time.delta_secs();
+    if max_turn > diff.abs() {
+      ship.pitch = ship.target_pitch;
+    } else {
+      ship.pitch += diff.signum() * max_turn;
+    }
+    transform.rotation = Quat::from_rotation_y(ship.pitch);
   }
-  transform.rotation = Quat::from_rotation_y(ship.pitch);
 }
 
 fn fire_controls(
   mut commands: Commands,
-  mut query: Query<(Entity, &mut PlayerShip, &Velocity)>,
-
-  mut invinciblitiy_query: Query<&mut Invincible>,
-  mut ev_trigger_event: EventReader<InputTriggerEvent>,
+  mut query: Query<(Entity, &mut PlayerShip, &PlayerNumber, &Velocity, &Children)>,
+  mut invincible_query: Query<&mut Invincible>,
+  controls: Res<PlayerControls>,
   mut display_hook_query: Query<(&mut Visibility, &GlobalTransform), With<DisplayHook>>,
   mut hook_query: Query<&mut Hook>,
   scene_assets: Res<SceneAssets>,
 ) {
-  let Ok((entity, mut ship, velocity)) = query.single_mut() else {
-    return;
-  };
+  for (entity, mut ship, number, velocity, children) in query.iter_mut() {
+    let controls = controls.get(number.0);
+    match ship.hook {
+      //shooting again calls it back too
+      Some(hook) if controls.recall || controls.shoot => {
+        let Ok(mut hook_state) = hook_query.get_mut(hook) else {
+          continue;
+        };
+        hook_state.returning = true;
+      }
+      //only the one claw, nothing to fire while it's holding something
+      None if controls.shoot && ship.captive.is_none() => {
+        let mut display_hooks = display_hook_query.iter_many_mut(children);
+        let Some((mut display_hook_visible, transform)) = display_hooks.fetch_next() else {
+          continue;
+        };
+        *display_hook_visible = Visibility::Hidden;
+        ship.hook = Some(
+          commands
+            .spawn(hook_builder(
+              entity,
+              *number,
+              transform.translation(),
+              velocity.0,
+              scene_assets.hook.clone(),
+            ))
+            .id(),
+        );
 
-  let mut shoot = false;
-  for InputTriggerEvent { action, input_type } in ev_trigger_event.read() {
-    if *action == InputEventAction::Shoot && *input_type == InputEventType::Pressed {
-      shoot = true;
-    }
-  }
-  if !shoot {
-    return;
-  }
-  match ship.hook {
-    Some(hook) => {
-      let Ok(mut hook_state) = hook_query.get_mut(hook) else {
-        return;
-      };
-      hook_state.returning = true;
-    }
-    None => {
-      match ship.captive {
-        Some(captive) => {
-          //eat captive?
-          commands.entity(captive).despawn();
-          ship.captive = None;
-        }
-        None => {
-          let Ok((mut display_hook_visible, transform)) = display_hook_query.single_mut()
-          else {
-            return;
-          };
-          *display_hook_visible = Visibility::Hidden;
-          ship.hook = Some(
-            commands
-              .spawn(hook_builder(
-                entity,
-                transform.translation(),
-                velocity.0,
-                scene_assets.hook.clone(),
-              ))
-              .id(),
-          );
-
-          //remove invincible if present
-          if let Ok(mut invincibility) = invinciblitiy_query.single_mut() {
-            let time = invincibility.time.duration();
-            invincibility.time.set_elapsed(time);
-          }
+        //remove invincible if present
+        if let Ok(mut invincibility) = invincible_query.get_mut(entity) {
+          let time = invincibility.time.duration();
+          invincibility.time.set_elapsed(time);
         }
       }
+      _ => {}
+    }
+  }
+}
+
+fn captive_controls(
+  mut commands: Commands,
+  mut query: Query<(Entity, &mut PlayerShip, &PlayerNumber, &Velocity)>,
+  controls: Res<PlayerControls>,
+  captive_query: Query<&GlobalTransform, With<Captured>>,
+  mut ev_health_writer: EventWriter<HealthEvent>,
+  mut ev_sfx_writer: EventWriter<SfxEvent>,
+) {
+  for (entity, mut ship, number, velocity) in query.iter_mut() {
+    let Some(captive) = ship.captive else {
+      continue;
+    };
+    let Ok(transform) = captive_query.get(captive) else {
+      continue;
+    };
+    let controls = controls.get(number.0);
+
+    if controls.jettison {
+      //cut loose where it is and sent flying, still on our side
+      commands
+        .entity(captive)
+        .remove_parent_in_place()
+        .remove::<(Captured, Hooked, Acceleration)>()
+        .insert((
+          Jettisoned,
+          Velocity(velocity.0 + Vec3::new(-CAPTIVE_JETTISON_SPEED, 0., 0.)),
+          BoundsDespawn,
+        ));
+      ship.captive = None;
+      ev_sfx_writer.write(SfxEvent::new(Sfx::HookLaunch, transform.translation()));
+    } else if controls.consume {
+      commands.entity(captive).despawn();
+      ship.captive = None;
+      ev_health_writer.write(HealthEvent::new(
+        entity,
+        CAPTIVE_CONSUME_HEALTH,
+        DamageSource::Neutral,
+      ));
+      ev_sfx_writer.write(SfxEvent::new(Sfx::CaptiveEaten, transform.translation()));
     }
   }
 }
 
 fn movement_controls(
-  mut query: Query<(&mut Acceleration, &mut PlayerShip)>,
-  mut ev_movement_event: EventReader<InputMovementEvent>,
+  mut query: Query<(&mut Acceleration, &mut PlayerShip, &PlayerNumber)>,
+  heavy_query: Query<&Heavy>,
+  controls: Res<PlayerControls>,
   //keyboard_input: Res<ButtonInput<KeyCode>>,
 ) {
-  let Ok((mut acceleration, mut ship)) = query.single_mut() else {
-    return;
-  };
-  let mut acc = Vec2::ZERO;
-  for InputMovementEvent { direction } in ev_movement_event.read() {
-    acc += direction;
+  for (mut acceleration, mut ship, number) in query.iter_mut() {
+    //hauling something heavy about slows the ship down
+    let load = ship
+      .captive
+      .and_then(|captive| heavy_query.get(captive).ok())
+      .map_or(1., |heavy| heavy.0);
+    acceleration.max_speed = SHIP_MAX_SPEED * load;
+    //analog sticks give part thrust, everything else is flat out
+    let acc = controls.get(number.0).movement.clamp_length_max(1.);
+    acceleration.acceleration = Vec3::new(acc.x, 0., acc.y) * SHIP_ACCELERATION * load;
+    ship.target_pitch = acc.y * SHIP_MAX_PITCH;
   }
-
-  acc = acc.normalize_or_zero();
-  acceleration.acceleration = Vec3::new(acc.x, 0., acc.y) * SHIP_ACCELERATION;
-  ship.target_pitch = acc.y * SHIP_MAX_PITCH;
 }
 
 fn bounds_check(mut query: Query<&mut Transform, With<PlayerShip>>) {
-  let Ok(mut transform) = query.single_mut() else {
-    return;
-  };
-
-  transform.translation.x = transform.translation.x.clamp(BOUNDS_X_MIN, BOUNDS_X_MAX);
-  transform.translation.z = transform.translation.z.clamp(BOUNDS_Z_MIN, BOUNDS_Z_MAX);
+  for mut transform in query.iter_mut() {
+    transform.translation.x = transform.translation.x.clamp(BOUNDS_X_MIN, BOUNDS_X_MAX);
+    transform.translation.z = transform.translation.z.clamp(BOUNDS_Z_MIN, BOUNDS_Z_MAX);
+  }
 }
 
 fn retrieve_hook(
   mut commands: Commands,
   mut ev_hook_returned: EventReader<HookReturnedEvent>,
   mut display_hook_query: Query<&mut Visibility, With<DisplayHook>>,
-  mut ship_query: Query<(&mut PlayerShip, Entity)>,
+  mut ship_query: Query<(&mut PlayerShip, Entity, &PlayerNumber, &Children)>,
   mut target_query: Query<(&mut Transform, &mut Hookable)>,
 ) {
-  for &HookReturnedEvent { target } in ev_hook_returned.read() {
-    let Ok(mut visible) = display_hook_query.single_mut() else {
-      return;
-    };
-    let Ok((mut ship, ship_entity)) = ship_query.single_mut() else {
-      return;
+  for &HookReturnedEvent { owner, target, .. } in ev_hook_returned.read() {
+    let Ok((mut ship, ship_entity, number, children)) = ship_query.get_mut(owner) else {
+      continue;
     };
     ship.hook = None;
-    *visible = Visibility::Visible;
+    let mut display_hooks = display_hook_query.iter_many_mut(children);
+    while let Some(mut visible) = display_hooks.fetch_next() {
+      *visible = Visibility::Visible;
+    }
 
     info!("ship hook returned, captive: {:?}", target);
 
     if target.is_some() {
       let target_entity = target.unwrap();
       let Ok((mut transform, mut hookable)) = target_query.get_mut(target_entity) else {
-        return;
+        continue;
       };
 
       ship.captive = Some(target_entity);
@@ -269,7 +369,8 @@ fn retrieve_hook(
         Captured {
           captor: ship_entity,
         },
-        Player,
+        CollisionLayer::Captive,
+        *number,
       ));
     }
   }
@@ -280,49 +381,137 @@ fn remove_dead_captive(
   mut query: Query<(Entity, &Captured, &Health), Without<PlayerShip>>,
   mut ship_query: Query<&mut PlayerShip>,
 ) {
-  let Ok((captive_entity, captured, health)) = query.single_mut() else {
-    return;
-  };
-  if health.value <= 0. {
+  for (captive_entity, captured, health) in query.iter_mut() {
+    if health.value > 0. {
+      continue;
+    }
     info!("removing dead captive: {:?}", captive_entity);
     commands
       .entity(captured.captor)
       .remove_children(&[captive_entity]);
     let Ok(mut ship) = ship_query.get_mut(captured.captor) else {
-      return;
+      continue;
     };
     ship.captive = None;
   }
 }
 
-fn check_dead(
+//ship's wreck is handled like everything else, just tidy up after it
+fn ship_destroyed(
+  trigger: Trigger<DeathEvent>,
   mut commands: Commands,
-  query: Query<(Entity, &Health, &GlobalTransform, &Velocity), (With<PlayerShip>, Without<Wreck>)>,
-  hook_query: Query<Entity, With<Hook>>,
-  mut ev_wreck_writer: EventWriter<WreckedEvent>,
-  mut play_state: ResMut<NextState<PlayState>>,
-  scene_assets: Res<SceneAssets>,
+  ship_query: Query<&PlayerShip>,
 ) {
-  for (entity, health, transform, velocity) in query.iter() {
-    if health.value <= 0. {
-      info!("dead");
-      //   ev_splosion_writer.send(SplosionEvent::new(transform.translation(), 3.0,velocity.0));
-      ev_wreck_writer.write(WreckedEvent::new(
-        scene_assets.ship.clone(),
-        transform.translation(),
-        transform.rotation(),
-        velocity.0,
-        1.2,
-        3.0,
-        3.0,
-      ));
-      commands.entity(entity).despawn();
-      play_state.set(PlayState::Dead);
-      //get rid of any floating hooks
-      let Ok(hook_entity) = hook_query.single() else {
-        continue;
-      };
-      commands.entity(hook_entity).despawn();
-    }
+  let Ok(ship) = ship_query.get(trigger.target()) else {
+    return;
+  };
+  info!("ship destroyed at {:?}", trigger.translation);
+  //get rid of its hook if it's out
+  if let Some(hook_entity) = ship.hook {
+    commands.entity(hook_entity).try_despawn();
+  }
+}
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    enemy::{EnemyKind, SpawnEnemyEvent},
+    headless::HeadlessApp,
+    input::{InputEventAction, InputEventType, InputTriggerEvent},
+  };
+
+  #[test]
+  fn hooked_mine_is_captured_by_ship() {
+    let mut game = HeadlessApp::new();
+    let ship = game.find::<PlayerShip>().expect("ship spawned");
+
+    //sat still in the path of the hook
+    game.send(SpawnEnemyEvent::new(
+      EnemyKind::Mine,
+      STARTING_TRANSLATION + CLAW_OFFSET + Vec3::new(-15., 0., 0.),
+      Some(Vec3::ZERO),
+    ));
+    game.step();
+    let mine = game.find::<Hookable>().expect("mine spawned");
+
+    game.send(InputTriggerEvent::new(
+      InputEventAction::Shoot,
+      InputEventType::Pressed,
+    ));
+    game.step_secs(2.);
+
+    let captured = game.world().get::<Captured>(mine).expect("mine captured");
+    assert_eq!(captured.captor, ship);
+    assert_eq!(
+      game.world().get::<ChildOf>(mine).map(ChildOf::parent),
+      Some(ship)
+    );
+    assert!(game.find::<Hook>().is_none());
+  }
+
+  #[test]
+  fn jettisoned_captive_flies_off_ahead() {
+    let mut game = HeadlessApp::new();
+    game.send(SpawnEnemyEvent::new(
+      EnemyKind::Mine,
+      STARTING_TRANSLATION + CLAW_OFFSET + Vec3::new(-15., 0., 0.),
+      Some(Vec3::ZERO),
+    ));
+    game.step();
+    let mine = game.find::<Hookable>().expect("mine spawned");
+    game.send(InputTriggerEvent::new(
+      InputEventAction::Shoot,
+      InputEventType::Pressed,
+    ));
+    game.step_secs(2.);
+    assert!(game.world().get::<Captured>(mine).is_some());
+
+    //shooting with a captive held doesn't get rid of it any more
+    game.send(InputTriggerEvent::new(
+      InputEventAction::Shoot,
+      InputEventType::Pressed,
+    ));
+    game.step();
+    assert!(game.world().get::<Captured>(mine).is_some());
+
+    let held_at = game
+      .world()
+      .get::<GlobalTransform>(mine)
+      .unwrap()
+      .translation();
+    game.send(InputTriggerEvent::new(
+      InputEventAction::Jettison,
+      InputEventType::Pressed,
+    ));
+    game.step_secs(0.2);
+    let world = game.world();
+    assert!(world.get::<Captured>(mine).is_none());
+    assert!(world.get::<ChildOf>(mine).is_none());
+    assert!(world.get::<Jettisoned>(mine).is_some());
+    let thrown_to = world.get::<GlobalTransform>(mine).unwrap().translation();
+    assert!(thrown_to.x < held_at.x - 5.);
+  }
+
+  #[test]
+  fn each_player_fires_their_own_hook() {
+    let mut game = HeadlessApp::with_players(2);
+    game
+      .send(InputTriggerEvent::new(InputEventAction::Shoot, InputEventType::Pressed).for_player(1));
+    game.step_frames(2);
+
+    let world = game.app.world_mut();
+    let hooks: Vec<_> = world
+      .query_filtered::<&PlayerNumber, With<Hook>>()
+      .iter(world)
+      .copied()
+      .collect();
+    assert_eq!(hooks, [PlayerNumber(1)]);
+    let ships: Vec<_> = world
+      .query::<(&PlayerShip, &PlayerNumber)>()
+      .iter(world)
+      .map(|(ship, number)| (number.0, ship.hook.is_some()))
+      .collect();
+    assert!(ships.contains(&(0, false)));
+    assert!(ships.contains(&(1, true)));
   }
 }
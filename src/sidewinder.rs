@@ -1,37 +1,52 @@
 use bevy::prelude::*;
-use rand::Rng;
-use std::{f32::consts::PI, time::Duration};
+use std::f32::consts::PI;
 
 use crate::{
   asset_loader::SceneAssets,
   bounds_check::BoundsDespawn,
-  bullet::ShootEvent,
+  projectile::ProjectileKind,
+  collider_shape::ColliderShape,
   collision_detection::Collider,
+  death::{Destroyable, Remains},
   enemy::*,
-  game_manager::PointEvent,
   health::Health,
   hit_marker::HitMarker,
-  hook::{Hookable, Hooked},
+  hook::Hookable,
   movement::{Roller, Velocity},
   scheduling::GameSchedule,
-  ship::Captured,
-  wreck::{Wreck, WreckedEvent},
+  weapon::{Weapon, WeaponProfile},
 };
 
-const SIDEWINDER_SPAWN_TIME_SECONDS: f32 = 2.;
 const SIDEWINDER_SPIN_SPEED: f32 = 3.0;
 const SIDEWINDER_VERTICAL_VARIANCE: f32 = 10.0;
-const SIDEWINDER_SHOOT_SPEED: f32 = 16.0;
-const SIDEWINDER_COLLISION_RADIUS: f32 = 2.5;
+//half size of the box, the wings stick out along y and show as it spins
+const SIDEWINDER_COLLISION_SIZE: Vec3 = Vec3::new(2.9, 3.1, 0.85);
 const SIDEWINDER_COLLISION_DAMAGE: f32 = -25.0;
-const SIDEWINDER_BULLET_DAMAGE: f32 = -20.0;
-const SIDEWINDER_BULLET_SCALE: f32 = 1.0;
 const SIDEWINDER_HEALTH: f32 = 40.0;
 
-const SIDEWINDER_SHOOT_TIME: f32 = 1.7;
-const SIDEWINDER_CAPTURED_SHOOT_TIME: f32 = 0.5;
-const SIDEWINDER_CAPTURED_SHOOT_SPEED: f32 = 48.0;
+const SIDEWINDER_WEAPON: WeaponProfile = WeaponProfile {
+  fire_rate: 1. / 1.7,
+  projectiles: 1,
+  spread: 0.,
+  burst: 1,
+  burst_interval: 0.,
+  projectile_speed: 16.0,
+  damage: -20.0,
+  scale: 1.0,
+  muzzle_offset: Vec3::new(-3., 0., 0.),
+  projectile: ProjectileKind::Straight,
+};
+//a captured one gives its captor a spread of shots that come back off the edges
+const SIDEWINDER_CAPTURED_WEAPON: WeaponProfile = WeaponProfile {
+  fire_rate: 2.0,
+  projectiles: 3,
+  spread: 0.4,
+  projectile_speed: 48.0,
+  projectile: ProjectileKind::Bouncing,
+  ..SIDEWINDER_WEAPON
+};
 const SIDEWINDER_BLAST_SIZE: f32 = 3.0;
+const SIDEWINDER_WRECK_TIME: f32 = 1.5;
 
 const SIDEWINDER_HOOK_TRANSLATION: Vec3 = Vec3::new(-3., 0., 0.);
 const SIDEWINDER_HOOK_ROTATION: f32 = 0.0;
@@ -42,141 +57,104 @@ pub struct SidewinderPlugin;
 
 impl Plugin for SidewinderPlugin {
   fn build(&self, app: &mut App) {
-    app
-      .add_systems(
-        Update,
-        (spawn_sidewinder, shoot, shoot_captured).in_set(GameSchedule::EntityUpdates),
-      )
-      .add_systems(Update, check_dead.in_set(GameSchedule::DespawnEntities));
-  }
-}
-
-#[derive(Deref, DerefMut)]
-pub struct SpawnTimer(Timer);
-
-impl Default for SpawnTimer {
-  fn default() -> Self {
-    Self(Timer::from_seconds(
-      SIDEWINDER_SPAWN_TIME_SECONDS,
-      TimerMode::Repeating,
-    ))
+    app.add_systems(
+      FixedUpdate,
+      spawn_sidewinder.in_set(GameSchedule::EntityUpdates),
+    );
   }
 }
 
 #[derive(Component)]
 #[require(Enemy, BoundsDespawn, Hookable, HitMarker)]
-struct Sidewinder {
-  shoot_timer: Timer,
-}
-
-fn shoot_captured(
-  mut query: Query<(&mut Sidewinder, &GlobalTransform), With<Captured>>,
-  //captor_query: Query<&Velocity>,
-  time: Res<Time>,
-  mut ev_shoot_event_writer: EventWriter<ShootEvent>,
-) {
-  for (mut sidewinder, transform) in &mut query {
-    sidewinder
-      .shoot_timer
-      .set_duration(Duration::from_secs_f32(SIDEWINDER_CAPTURED_SHOOT_TIME));
-    sidewinder.shoot_timer.tick(time.delta());
-    if sidewinder.shoot_timer.finished() {
-      //info!("Shooting");
-      ev_shoot_event_writer.write(ShootEvent::new(
-        true,
-        transform.translation() + (transform.left() * 3.0),
-        transform.left() * SIDEWINDER_CAPTURED_SHOOT_SPEED,
-        SIDEWINDER_BULLET_DAMAGE,
-        SIDEWINDER_BULLET_SCALE,
-      ));
-    }
-  }
-}
-
-fn shoot(
-  mut query: Query<
-    (&mut Sidewinder, &GlobalTransform, &Velocity),
-    (Without<Hooked>, Without<Captured>),
-  >,
-  time: Res<Time>,
-  mut ev_shoot_event_writer: EventWriter<ShootEvent>,
-) {
-  for (mut sidewinder, transform, velocity) in &mut query {
-    sidewinder.shoot_timer.tick(time.delta());
-    if sidewinder.shoot_timer.finished() {
-      //info!("Shooting");
+struct Sidewinder;
 
-      ev_shoot_event_writer.write(ShootEvent::new(
-        false,
-        transform.translation() + (transform.left() * 3.0),
-        velocity.0 + (transform.left() * SIDEWINDER_SHOOT_SPEED),
-        SIDEWINDER_BULLET_DAMAGE,
-        SIDEWINDER_BULLET_SCALE,
-      ));
-    }
-  }
-}
-
-fn check_dead(
+fn spawn_sidewinder(
   mut commands: Commands,
-  query: Query<(Entity, &Health, &GlobalTransform, &Velocity), (With<Sidewinder>, Without<Wreck>)>,
-  mut ev_wreck_writer: EventWriter<WreckedEvent>,
-  mut ev_point_writer: EventWriter<PointEvent>,
+  mut ev_spawn_enemy_reader: EventReader<SpawnEnemyEvent>,
   scene_assets: Res<SceneAssets>,
 ) {
-  for (entity, health, transform, velocity) in query.iter() {
-    if health.value <= 0. {
-      info!("dead {:?}", entity);
-      //   ev_splosion_writer.send(SplosionEvent::new(transform.translation(), 3.0,velocity.0));
-      ev_wreck_writer.write(WreckedEvent::new(
-        scene_assets.sidewinder.clone(),
-        transform.translation(),
-        transform.rotation(),
-        velocity.0,
-        SIDEWINDER_SPIN_SPEED,
-        1.5,
-        SIDEWINDER_BLAST_SIZE,
-      ));
-      commands.entity(entity).despawn();
-      ev_point_writer.write(PointEvent(SIDEWINDER_POINTS));
+  for &SpawnEnemyEvent {
+    kind,
+    translation,
+    velocity,
+  } in ev_spawn_enemy_reader.read()
+  {
+    if kind != EnemyKind::Sidewinder {
+      continue;
     }
+    //by default angle in towards the middle of the screen
+    let velocity = velocity.unwrap_or_else(|| {
+      let spawn_pos = translation.z / ENEMY_START_POINT_Z_BOUNDS_MAX;
+      Vec3::new(20.0, 0., spawn_pos * -SIDEWINDER_VERTICAL_VARIANCE)
+    });
+
+    //info!("Spawn sidewinder");
+    commands.spawn((
+      Sidewinder,
+      Weapon::new(Some(SIDEWINDER_WEAPON), Some(SIDEWINDER_CAPTURED_WEAPON)),
+      SceneRoot(scene_assets.sidewinder.clone()),
+      Transform::from_translation(translation).with_rotation(Quat::from_rotation_z(PI)),
+      Velocity(velocity),
+      Collider::shaped(
+        ColliderShape::Box(SIDEWINDER_COLLISION_SIZE),
+        SIDEWINDER_COLLISION_DAMAGE,
+      ),
+      Hookable::new(
+        SIDEWINDER_HOOK_TRANSLATION,
+        Quat::from_rotation_z(SIDEWINDER_HOOK_ROTATION),
+      ),
+      Health::new(SIDEWINDER_HEALTH),
+      Destroyable::new(
+        Remains::Wreck {
+          scene: scene_assets.sidewinder.clone(),
+          roll_speed: SIDEWINDER_SPIN_SPEED,
+          time_to_live: SIDEWINDER_WRECK_TIME,
+        },
+        SIDEWINDER_BLAST_SIZE,
+        SIDEWINDER_POINTS,
+      ),
+      Roller::new(SIDEWINDER_SPIN_SPEED, 0., 0.),
+    ));
   }
 }
 
-fn spawn_sidewinder(
-  mut commands: Commands,
-  time: Res<Time>,
-  mut timer: Local<SpawnTimer>,
-  scene_assets: Res<SceneAssets>,
-) {
-  timer.tick(time.delta());
-  if !timer.just_finished() {
-    return;
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    game_manager::{Game, PointEvent},
+    headless::HeadlessApp,
+    health::{DamageSource, HealthEvent},
+    wreck::WreckedEvent,
+  };
+
+  #[test]
+  fn destroyed_sidewinder_wrecks_and_scores() {
+    let mut game = HeadlessApp::new();
+    game.record::<WreckedEvent>();
+    game.record::<PointEvent>();
+
+    game.send(SpawnEnemyEvent::new(
+      EnemyKind::Sidewinder,
+      Vec3::new(-20., 0., 0.),
+      Some(Vec3::ZERO),
+    ));
+    game.step();
+    let sidewinder = game.find::<Sidewinder>().expect("sidewinder spawned");
+
+    game.send(HealthEvent::new(
+      sidewinder,
+      -SIDEWINDER_HEALTH,
+      DamageSource::ShipRam(0),
+    ));
+    game.step_frames(2);
+
+    assert!(game.world().get_entity(sidewinder).is_err());
+    assert_eq!(game.recorded::<WreckedEvent>().len(), 1);
+    let points = game.recorded::<PointEvent>();
+    assert_eq!(points.len(), 1);
+    assert_eq!(points[0].points, SIDEWINDER_POINTS);
+    assert_eq!(points[0].source, DamageSource::ShipRam(0));
+    assert_eq!(game.single::<Game>().score, SIDEWINDER_POINTS);
   }
-
-  let mut rng = rand::thread_rng();
-  let spawn_pos = rng.gen_range(-1. ..1.);
-  let start_z = ENEMY_START_POINT_Z_BOUNDS_MAX * spawn_pos;
-  let vel_z = spawn_pos * -SIDEWINDER_VERTICAL_VARIANCE;
-
-  //info!("Spawn sidewinder");
-  commands.spawn((
-    Sidewinder {
-      shoot_timer: Timer::from_seconds(SIDEWINDER_SHOOT_TIME, TimerMode::Repeating),
-    },
-    SceneRoot(scene_assets.sidewinder.clone()),
-    Transform::from_translation(Vec3::new(ENEMY_START_POINT_X, 0., start_z))
-      .with_rotation(Quat::from_rotation_z(PI)),
-    Velocity(Vec3::new(20.0, 0., vel_z)),
-    Collider {
-      radius: SIDEWINDER_COLLISION_RADIUS,
-      collision_damage: SIDEWINDER_COLLISION_DAMAGE,
-    },
-    Hookable::new(
-      SIDEWINDER_HOOK_TRANSLATION,
-      Quat::from_rotation_z(SIDEWINDER_HOOK_ROTATION),
-    ),
-    Health::new(SIDEWINDER_HEALTH),
-    Roller::new(SIDEWINDER_SPIN_SPEED, 0.,0.),
-  ));
 }
@@ -0,0 +1,78 @@
+//! broad phase for collision detection, colliders bucketed by where they are on the
+//! play plane so each check only looks at what's close by
+use bevy::{platform::collections::HashMap, prelude::*};
+
+//a little bigger than most colliders, so most lookups only touch the cells next door
+const SPATIAL_GRID_CELL_SIZE: f32 = 8.0;
+
+///uniform grid over x / z, rebuilt every step
+#[derive(Resource, Default)]
+pub struct SpatialGrid {
+  cells: HashMap<IVec2, Vec<Entity>>,
+  ///biggest collider in the grid, how far outside its cell something can still reach
+  max_radius: f32,
+}
+
+fn cell(translation: Vec3) -> IVec2 {
+  IVec2::new(
+    (translation.x / SPATIAL_GRID_CELL_SIZE).floor() as i32,
+    (translation.z / SPATIAL_GRID_CELL_SIZE).floor() as i32,
+  )
+}
+
+impl SpatialGrid {
+  ///empty it out, keeping hold of the cells that were in use
+  pub fn clear(&mut self) {
+    self.cells.retain(|_, entities| {
+      let used = !entities.is_empty();
+      entities.clear();
+      used
+    });
+    self.max_radius = 0.;
+  }
+
+  ///goes in the cell its centre is in, however big it is
+  pub fn insert(&mut self, entity: Entity, translation: Vec3, radius: f32) {
+    self
+      .cells
+      .entry(cell(translation))
+      .or_default()
+      .push(entity);
+    self.max_radius = self.max_radius.max(radius);
+  }
+
+  ///everything that could be touching a circle, each only once, in a fixed order.
+  ///a point is a circle with no radius
+  pub fn nearby(&self, translation: Vec3, radius: f32) -> impl Iterator<Item = Entity> + '_ {
+    let reach = Vec3::splat(radius + self.max_radius);
+    let min = cell(translation - reach);
+    let max = cell(translation + reach);
+    (min.x..=max.x)
+      .flat_map(move |x| (min.y..=max.y).map(move |z| IVec2::new(x, z)))
+      .filter_map(|cell| self.cells.get(&cell))
+      .flatten()
+      .copied()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn nearby_reaches_big_colliders_in_other_cells() {
+    let small = Entity::from_raw(1);
+    let big = Entity::from_raw(2);
+    let far = Entity::from_raw(3);
+    let mut grid = SpatialGrid::default();
+    grid.insert(small, Vec3::new(1., 0., 1.), 1.);
+    grid.insert(big, Vec3::new(-10., 0., 1.), 5.);
+    grid.insert(far, Vec3::new(60., 0., -40.), 1.);
+
+    let found: Vec<_> = grid.nearby(Vec3::new(-4., 0., 1.), 0.).collect();
+    assert_eq!(found, [big, small]);
+
+    grid.clear();
+    assert_eq!(grid.nearby(Vec3::new(1., 0., 1.), 0.).count(), 0);
+  }
+}
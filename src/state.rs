@@ -5,6 +5,7 @@ impl Plugin for StatePlugin {
   fn build(&self, app: &mut App) {
     app
       .init_state::<GameState>()
+      .add_sub_state::<PauseState>()
       .add_systems(PreStartup, init_game_state)
       .add_systems(Update, update_game_state)
       .add_event::<GameStateEvent>();
@@ -23,12 +24,23 @@ impl GameStateEvent {
 }
 
 #[derive(States, Debug, Clone, PartialEq, Eq, Hash, Default, Copy)]
+#[states(scoped_entities)]
 pub enum GameState {
   #[default]
   Loading,
+  Menu,
   Playing,
+  GameOver,
   Shutdown,
-  // Paused,
+}
+
+#[derive(SubStates, Debug, Clone, PartialEq, Eq, Hash, Default, Copy)]
+#[source(GameState = GameState::Playing)]
+#[states(scoped_entities)]
+pub enum PauseState {
+  #[default]
+  Running,
+  Paused,
 }
 
 fn init_game_state(mut next_state: ResMut<NextState<GameState>>) {
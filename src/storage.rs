@@ -0,0 +1,77 @@
+//! small bits of data kept between runs, a file in the user data folder on desktop,
+//! app private storage on android and localStorage in the browser
+use bevy::prelude::*;
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::{fs, path::PathBuf};
+
+#[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+const STORAGE_FOLDER: &str = "claw2";
+#[cfg(target_arch = "wasm32")]
+const STORAGE_PREFIX: &str = "claw2.";
+
+#[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+fn storage_dir() -> Option<PathBuf> {
+  dirs::data_dir().map(|dir| dir.join(STORAGE_FOLDER))
+}
+
+#[cfg(target_os = "android")]
+fn storage_dir() -> Option<PathBuf> {
+  bevy::window::ANDROID_APP.get()?.internal_data_path()
+}
+
+///stored value for the key, None if it's never been saved or can't be read
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load(key: &str) -> Option<String> {
+  let path = storage_dir()?.join(format!("{}.json", key));
+  fs::read_to_string(path).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save(key: &str, value: &str) {
+  let Some(dir) = storage_dir() else {
+    warn!("nowhere to save {:?}", key);
+    return;
+  };
+  let path = dir.join(format!("{}.json", key));
+  if let Err(err) = fs::create_dir_all(&dir).and_then(|_| fs::write(&path, value)) {
+    warn!("failed saving {:?}: {:?}", path, err);
+  }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+  web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn load(key: &str) -> Option<String> {
+  local_storage()?
+    .get_item(&format!("{}{}", STORAGE_PREFIX, key))
+    .ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn save(key: &str, value: &str) {
+  let Some(storage) = local_storage() else {
+    warn!("no local storage to save {:?}", key);
+    return;
+  };
+  if let Err(err) = storage.set_item(&format!("{}{}", STORAGE_PREFIX, key), value) {
+    warn!("failed saving {:?}: {:?}", key, err);
+  }
+}
+
+///seconds since the unix epoch
+#[cfg(not(target_arch = "wasm32"))]
+pub fn now_secs() -> u64 {
+  std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .map_or(0, |time| time.as_secs())
+}
+
+//std time isn't available in the browser
+#[cfg(target_arch = "wasm32")]
+pub fn now_secs() -> u64 {
+  (js_sys::Date::now() / 1000.) as u64
+}
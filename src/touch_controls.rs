@@ -0,0 +1,297 @@
+use bevy::{prelude::*, ui::ComputedNode};
+
+use crate::{
+  asset_loader::SceneAssets,
+  input::{
+    gather_controls, InputEventAction, InputEventType, InputMovementEvent, InputTriggerEvent,
+    OnScreenControls,
+  },
+  scheduling::GameSchedule,
+  settings::{Settings, TOUCH_STEPS},
+  state::{GameState, PauseState},
+};
+
+const TOUCH_STICK_SIZE: f32 = 160.;
+const TOUCH_KNOB_SIZE: f32 = 64.;
+const TOUCH_BUTTON_SIZE: f32 = 76.;
+const TOUCH_PAUSE_SIZE: f32 = 48.;
+const TOUCH_MARGIN: f32 = 24.;
+const TOUCH_LABEL_FONT_SIZE: f32 = 16.;
+const TOUCH_COLOUR: Color = Color::srgb(0.8, 0.8, 0.9);
+//opacity at the top setting, held controls light up to double it
+const TOUCH_MAX_ALPHA: f32 = 0.5;
+//how far the stick has to be pushed before the ship moves, as a fraction of its reach
+const TOUCH_STICK_DEADZONE: f32 = 0.15;
+
+pub struct TouchControlsPlugin;
+
+impl Plugin for TouchControlsPlugin {
+  fn build(&self, app: &mut App) {
+    app
+      .add_systems(OnEnter(GameState::Playing), spawn_touch_controls)
+      .add_systems(OnExit(GameState::Playing), hide_touch_controls)
+      .add_systems(
+        Update,
+        read_touch_controls
+          .in_set(GameSchedule::ReadInput)
+          .before(gather_controls)
+          .run_if(in_state(PauseState::Running)),
+      )
+      .add_systems(
+        Update,
+        (move_knobs, highlight_touch_controls).run_if(in_state(GameState::Playing)),
+      );
+  }
+}
+
+///on screen thumbstick, held by whichever finger first landed on it
+#[derive(Component, Default)]
+struct TouchStick {
+  finger: Option<u64>,
+  ///-1 to 1 in screen directions, y down
+  direction: Vec2,
+}
+
+#[derive(Component)]
+struct TouchKnob {
+  //how far it can move from the middle of the stick in pixels
+  travel: f32,
+}
+
+#[derive(Component)]
+struct TouchButton {
+  action: InputEventAction,
+  finger: Option<u64>,
+}
+
+impl TouchButton {
+  fn new(action: InputEventAction) -> Self {
+    Self {
+      action,
+      finger: None,
+    }
+  }
+}
+
+fn touch_alpha(settings: &Settings, held: bool) -> f32 {
+  let alpha = TOUCH_MAX_ALPHA * settings.touch_opacity as f32 / TOUCH_STEPS as f32;
+  if held {
+    (alpha * 2.).min(1.)
+  } else {
+    alpha
+  }
+}
+
+fn round_node(size: f32) -> Node {
+  Node {
+    width: Val::Px(size),
+    height: Val::Px(size),
+    justify_content: JustifyContent::Center,
+    align_items: AlignItems::Center,
+    ..default()
+  }
+}
+
+fn spawn_touch_controls(
+  mut commands: Commands,
+  settings: Res<Settings>,
+  scene_assets: Res<SceneAssets>,
+  mut on_screen: ResMut<OnScreenControls>,
+) {
+  on_screen.0 = settings.touch_controls;
+  if !settings.touch_controls {
+    return;
+  }
+
+  //the middle step is the standard size
+  let scale = (settings.touch_size + 1) as f32 / 4.;
+  let stick_size = TOUCH_STICK_SIZE * scale;
+  let knob_size = TOUCH_KNOB_SIZE * scale;
+  let button_size = TOUCH_BUTTON_SIZE * scale;
+  let colour = TOUCH_COLOUR.with_alpha(touch_alpha(&settings, false));
+  let margin = Val::Px(TOUCH_MARGIN);
+  //stick under the left thumb unless it's been swapped over, as left and right offsets
+  let (stick_side, button_side) = if settings.left_handed {
+    ((Val::Auto, margin), (margin, Val::Auto))
+  } else {
+    ((margin, Val::Auto), (Val::Auto, margin))
+  };
+  let button = |action: InputEventAction, label: &str, size: f32| {
+    (
+      TouchButton::new(action),
+      round_node(size),
+      BorderRadius::MAX,
+      BackgroundColor(colour),
+      children![(
+        Text::new(label),
+        TextFont {
+          font: scene_assets.font.clone(),
+          font_size: TOUCH_LABEL_FONT_SIZE * scale,
+          ..default()
+        },
+      )],
+    )
+  };
+
+  commands.spawn((
+    StateScoped(GameState::Playing),
+    TouchStick::default(),
+    Node {
+      position_type: PositionType::Absolute,
+      bottom: margin,
+      left: stick_side.0,
+      right: stick_side.1,
+      ..round_node(stick_size)
+    },
+    BorderRadius::MAX,
+    BackgroundColor(colour),
+    children![(
+      TouchKnob {
+        travel: (stick_size - knob_size) * 0.5,
+      },
+      Node {
+        position_type: PositionType::Absolute,
+        ..round_node(knob_size)
+      },
+      BorderRadius::MAX,
+      BackgroundColor(colour),
+    )],
+  ));
+
+  commands.spawn((
+    StateScoped(GameState::Playing),
+    Node {
+      position_type: PositionType::Absolute,
+      bottom: margin,
+      left: button_side.0,
+      right: button_side.1,
+      flex_direction: FlexDirection::Column,
+      align_items: AlignItems::Center,
+      row_gap: Val::Px(TOUCH_MARGIN * 0.5),
+      ..default()
+    },
+    children![
+      button(InputEventAction::Consume, "Eat", button_size * 0.75),
+      button(InputEventAction::Jettison, "Throw", button_size * 0.75),
+      button(InputEventAction::Shoot, "Hook", button_size),
+    ],
+  ));
+
+  commands.spawn((
+    StateScoped(GameState::Playing),
+    Node {
+      position_type: PositionType::Absolute,
+      top: margin,
+      left: button_side.0,
+      right: button_side.1,
+      ..default()
+    },
+    children![button(
+      InputEventAction::Pause,
+      "II",
+      TOUCH_PAUSE_SIZE * scale
+    )],
+  ));
+}
+
+fn hide_touch_controls(mut on_screen: ResMut<OnScreenControls>) {
+  on_screen.0 = false;
+}
+
+//centre and radius in logical pixels, the same space as touch positions
+fn touch_circle(node: &ComputedNode, transform: &GlobalTransform) -> (Vec2, f32) {
+  let centre = transform.translation().truncate() * node.inverse_scale_factor();
+  let radius = node.size().x * 0.5 * node.inverse_scale_factor();
+  (centre, radius)
+}
+
+fn touches_control(node: &ComputedNode, transform: &GlobalTransform, position: Vec2) -> bool {
+  let (centre, radius) = touch_circle(node, transform);
+  centre.distance_squared(position) < radius * radius
+}
+
+//each finger belongs to the control it landed on until it lifts
+fn read_touch_controls(
+  touches: Res<Touches>,
+  mut stick_query: Query<(&mut TouchStick, &ComputedNode, &GlobalTransform)>,
+  mut button_query: Query<(&mut TouchButton, &ComputedNode, &GlobalTransform)>,
+  mut ev_movement_writer: EventWriter<InputMovementEvent>,
+  mut ev_trigger_writer: EventWriter<InputTriggerEvent>,
+) {
+  for touch in touches.iter_just_pressed() {
+    let position = touch.position();
+    if let Some((mut stick, ..)) = stick_query.iter_mut().find(|(stick, node, transform)| {
+      stick.finger.is_none() && touches_control(node, transform, position)
+    }) {
+      stick.finger = Some(touch.id());
+      continue;
+    }
+    if let Some((mut button, ..)) = button_query.iter_mut().find(|(button, node, transform)| {
+      button.finger.is_none() && touches_control(node, transform, position)
+    }) {
+      button.finger = Some(touch.id());
+      ev_trigger_writer.write(InputTriggerEvent::new(
+        button.action,
+        InputEventType::Pressed,
+      ));
+    }
+  }
+
+  //lifted, cancelled or let go of while paused
+  for (mut button, ..) in button_query.iter_mut() {
+    let Some(finger) = button.finger else {
+      continue;
+    };
+    if touches.get_pressed(finger).is_none() {
+      button.finger = None;
+      ev_trigger_writer.write(InputTriggerEvent::new(
+        button.action,
+        InputEventType::Released,
+      ));
+    }
+  }
+
+  for (mut stick, node, transform) in stick_query.iter_mut() {
+    let Some(touch) = stick.finger.and_then(|finger| touches.get_pressed(finger)) else {
+      stick.finger = None;
+      stick.direction = Vec2::ZERO;
+      continue;
+    };
+    let (centre, radius) = touch_circle(node, transform);
+    stick.direction = ((touch.position() - centre) / radius).clamp_length_max(1.);
+    //screen y is down and movement x is flipped to match the play field
+    if stick.direction.length() > TOUCH_STICK_DEADZONE {
+      ev_movement_writer.write(InputMovementEvent::new(-stick.direction));
+    }
+  }
+}
+
+fn move_knobs(
+  stick_query: Query<(&TouchStick, &Children)>,
+  mut knob_query: Query<(&TouchKnob, &mut Node)>,
+) {
+  for (stick, children) in stick_query.iter() {
+    let mut knobs = knob_query.iter_many_mut(children);
+    while let Some((knob, mut node)) = knobs.fetch_next() {
+      node.left = Val::Px(knob.travel * (1. + stick.direction.x));
+      node.top = Val::Px(knob.travel * (1. + stick.direction.y));
+    }
+  }
+}
+
+fn highlight_touch_controls(
+  mut stick_query: Query<(&TouchStick, &mut BackgroundColor), Without<TouchButton>>,
+  mut button_query: Query<(&TouchButton, &mut BackgroundColor), Without<TouchStick>>,
+  settings: Res<Settings>,
+) {
+  for (stick, mut colour) in stick_query.iter_mut() {
+    colour
+      .0
+      .set_alpha(touch_alpha(&settings, stick.finger.is_some()));
+  }
+  for (button, mut colour) in button_query.iter_mut() {
+    colour
+      .0
+      .set_alpha(touch_alpha(&settings, button.finger.is_some()));
+  }
+}
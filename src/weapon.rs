@@ -0,0 +1,219 @@
+use bevy::prelude::*;
+use std::time::Duration;
+
+use crate::{
+  bullet::ShootEvent,
+  health::DamageSource,
+  hook::Hooked,
+  movement::Velocity,
+  projectile::{ProjectileKind, ProjectileRegistry},
+  scheduling::GameSchedule,
+  ship::{Captured, Jettisoned, PlayerNumber},
+};
+
+pub struct WeaponPlugin;
+
+impl Plugin for WeaponPlugin {
+  fn build(&self, app: &mut App) {
+    app.add_systems(
+      FixedUpdate,
+      fire_weapons.in_set(GameSchedule::EntityUpdates),
+    );
+  }
+}
+
+///how a gun fires
+#[derive(Clone, Copy, Debug)]
+pub struct WeaponProfile {
+  ///shots a second, each one a whole burst
+  pub fire_rate: f32,
+  ///projectiles in each shot, fanned out evenly across `spread` radians
+  pub projectiles: u32,
+  pub spread: f32,
+  ///shots in each burst, `burst_interval` seconds apart
+  pub burst: u32,
+  pub burst_interval: f32,
+  pub projectile_speed: f32,
+  pub damage: f32,
+  pub scale: f32,
+  ///where shots come from, in the shooter's own space. they head out along its -x
+  pub muzzle_offset: Vec3,
+  pub projectile: ProjectileKind,
+}
+
+impl WeaponProfile {
+  fn cooldown(&self) -> Duration {
+    Duration::from_secs_f32(1. / self.fire_rate)
+  }
+}
+
+///a gun an enemy carries, fired one way in the wild and another once it's been
+///captured and is fighting for a player
+#[derive(Component)]
+pub struct Weapon {
+  pub wild: Option<WeaponProfile>,
+  pub captured: Option<WeaponProfile>,
+  cooldown: Timer,
+  //shots still to come in the current burst
+  burst_remaining: u32,
+  burst_timer: Timer,
+}
+
+impl Weapon {
+  pub fn new(wild: Option<WeaponProfile>, captured: Option<WeaponProfile>) -> Self {
+    let first = wild.or(captured).map(|profile| profile.cooldown());
+    Self {
+      wild,
+      captured,
+      cooldown: Timer::new(first.unwrap_or_default(), TimerMode::Repeating),
+      burst_remaining: 0,
+      burst_timer: Timer::default(),
+    }
+  }
+}
+
+fn fire_weapons(
+  mut query: Query<(Entity, &mut Weapon, &GlobalTransform, &Velocity)>,
+  captive_query: Query<&PlayerNumber, With<Captured>>,
+  hooked_query: Query<(), With<Hooked>>,
+  jettisoned_query: Query<(), With<Jettisoned>>,
+  registry: Res<ProjectileRegistry>,
+  time: Res<Time>,
+  mut ev_shoot_event_writer: EventWriter<ShootEvent>,
+) {
+  for (entity, mut weapon, transform, velocity) in &mut query {
+    //captives are still hooked, anything else on the hook or thrown holds its fire
+    let (profile, source) = if let Ok(number) = captive_query.get(entity) {
+      (weapon.captured, DamageSource::CaptiveShot(number.0))
+    } else if hooked_query.contains(entity) || jettisoned_query.contains(entity) {
+      continue;
+    } else {
+      (weapon.wild, DamageSource::Enemy)
+    };
+    let Some(profile) = profile else {
+      continue;
+    };
+
+    weapon.cooldown.set_duration(profile.cooldown());
+    weapon.cooldown.tick(time.delta());
+    //first shot of a burst goes straight away, the rest follow on
+    if weapon.cooldown.just_finished() {
+      weapon.burst_remaining = profile.burst;
+      weapon.burst_timer = Timer::from_seconds(profile.burst_interval, TimerMode::Repeating);
+    } else {
+      weapon.burst_timer.tick(time.delta());
+      if !weapon.burst_timer.just_finished() {
+        continue;
+      }
+    }
+    if weapon.burst_remaining == 0 {
+      continue;
+    }
+    weapon.burst_remaining -= 1;
+
+    //kept on the play plane, however the shooter's rolled
+    let mut muzzle = transform.transform_point(profile.muzzle_offset);
+    muzzle.y = transform.translation().y;
+    let forward = transform.left().with_y(0.).normalize_or(Vec3::NEG_X);
+    //orbs leave slower than the profile says, the shooter's own speed carries over as it is
+    let speed =
+      profile.projectile_speed * registry.get(profile.projectile).map_or(1., |def| def.speed);
+    for index in 0..profile.projectiles {
+      //evenly across the spread, straight ahead for a single shot
+      let across = if profile.projectiles > 1 {
+        index as f32 / (profile.projectiles - 1) as f32 - 0.5
+      } else {
+        0.
+      };
+      let direction = Quat::from_rotation_y(across * profile.spread) * forward;
+      ev_shoot_event_writer.write(ShootEvent {
+        kind: profile.projectile,
+        ..ShootEvent::new(
+          source,
+          muzzle,
+          velocity.0 + direction * speed,
+          profile.damage,
+          profile.scale,
+        )
+      });
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{headless::HeadlessApp, ship::PlayerShip};
+
+  const WILD: WeaponProfile = WeaponProfile {
+    fire_rate: 0.1,
+    projectiles: 1,
+    spread: 0.,
+    burst: 1,
+    burst_interval: 0.,
+    projectile_speed: 10.,
+    damage: -1.,
+    scale: 1.,
+    muzzle_offset: Vec3::new(-2., 0., 0.),
+    projectile: ProjectileKind::Straight,
+  };
+
+  #[test]
+  fn captives_fire_their_captured_weapon_for_their_captor() {
+    let mut game = HeadlessApp::new();
+    game.record::<ShootEvent>();
+    let ship = game.find::<PlayerShip>().expect("ship spawned");
+    let captured = WeaponProfile {
+      fire_rate: 2.,
+      projectiles: 3,
+      spread: 1.,
+      burst: 2,
+      burst_interval: 0.1,
+      ..WILD
+    };
+    game.app.world_mut().spawn((
+      Weapon::new(Some(WILD), Some(captured)),
+      Transform::from_xyz(0., 0., -20.),
+      Velocity::default(),
+      Captured { captor: ship },
+      Hooked::default(),
+      PlayerNumber(1),
+    ));
+    game.step_secs(0.7);
+
+    //a burst of two, three across each
+    let shots = game.recorded::<ShootEvent>();
+    assert_eq!(shots.len(), 6);
+    assert!(shots
+      .iter()
+      .all(|shot| shot.source == DamageSource::CaptiveShot(1)));
+    assert!(shots
+      .iter()
+      .all(|shot| shot.start == Vec3::new(-2., 0., -20.)));
+    let middle = shots[1].velocity;
+    assert!(middle.abs_diff_eq(Vec3::new(-10., 0., 0.), 1e-4));
+    assert!(shots[0].velocity.angle_between(middle) > 0.4);
+  }
+
+  #[test]
+  fn orbs_leave_slowly_but_keep_the_shooters_speed() {
+    let mut game = HeadlessApp::new();
+    game.record::<ShootEvent>();
+    let orbs = WeaponProfile {
+      fire_rate: 10.,
+      projectile_speed: 20.,
+      projectile: ProjectileKind::Orb,
+      ..WILD
+    };
+    game.app.world_mut().spawn((
+      Weapon::new(Some(orbs), None),
+      Transform::from_xyz(30., 0., 0.),
+      Velocity(Vec3::new(0., 0., 8.)),
+    ));
+    game.step_secs(0.2);
+
+    let shots = game.recorded::<ShootEvent>();
+    assert!(!shots.is_empty());
+    assert!(shots[0].velocity.abs_diff_eq(Vec3::new(-10., 0., 8.), 1e-4));
+  }
+}
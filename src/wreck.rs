@@ -12,14 +12,14 @@ impl Plugin for WreckPlugin {
   fn build(&self, app: &mut App) {
     app
       .add_systems(Startup, init_wrecks)
-      .add_systems(Update, spawn_wrecks.in_set(GameSchedule::EntityUpdates))
-      .add_systems(Update, update_wrecks.in_set(GameSchedule::DespawnEntities))
+      .add_systems(FixedUpdate, spawn_wrecks.in_set(GameSchedule::EntityUpdates))
+      .add_systems(FixedUpdate, update_wrecks.in_set(GameSchedule::DespawnEntities))
       //.add_observer(add_wreck_material)
       .add_event::<WreckedEvent>();
   }
 }
 
-#[derive(Event)]
+#[derive(Event, Clone)]
 pub struct WreckedEvent {
   scene: Handle<Scene>,
   translation: Vec3,
@@ -0,0 +1,12 @@
+//! run with `cargo test --features headless`
+use bevy::prelude::*;
+use claw2::headless::HeadlessApp;
+
+#[test]
+fn the_game_runs_on_its_own_fixed_clock() {
+  let mut game = HeadlessApp::new();
+  let start = game.world().resource::<Time<Fixed>>().elapsed_secs_f64();
+  game.step_secs(2.);
+  let elapsed = game.world().resource::<Time<Fixed>>().elapsed_secs_f64() - start;
+  assert!((elapsed - 2.).abs() < 0.05, "ran for {elapsed}s");
+}